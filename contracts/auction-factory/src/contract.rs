@@ -0,0 +1,529 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, Deps, DepsMut, Env, Event, MessageInfo, Order, Reply, Response,
+    StdError, StdResult, Storage, SubMsg, SubMsgResult, Uint64, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cw_utils::parse_reply_instantiate_data;
+use dutch_auction_vault::msg::{
+    FeeUnverified as VaultFeeUnverified, InstantiateMsg as VaultInstantiateMsg,
+    MarketStateResponse, QueryMsg as VaultQueryMsg,
+};
+use dutch_auction_vault::state::Phase;
+use nft_auction::msg::{
+    AuctionInfoResponse, AuctionUnverified as NftAuctionUnverified,
+    FeeUnverified as NftFeeUnverified, InstantiateMsg as NftAuctionInstantiateMsg,
+    QueryMsg as NftAuctionQueryMsg,
+};
+
+use crate::error::ContractError;
+use crate::msg::{
+    AuctionResponse, AuctionStatus, AuctionsByStatusResponse, AuctionsResponse, ExecuteMsg,
+    FeeUnverified, InstantiateMsg, QueryMsg,
+};
+use crate::state::{
+    auctions, AuctionKind, AuctionRecord, Config, Fee, PendingAuction, CONFIG, NEXT_AUCTION_ID,
+    PENDING_AUCTION,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:auction-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const INSTANTIATE_AUCTION_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let config = Config {
+        admin: msg
+            .admin
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?
+            .unwrap_or(info.sender),
+        nft_auction_code_id: msg.nft_auction_code_id,
+        vault_code_id: msg.vault_code_id,
+        fees: check_fees(deps.api, msg.fees)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", config.admin))
+}
+
+/// validates the factory fees, which must add up to at most 10000 bps and
+/// have distinct labels
+fn check_fees(api: &dyn Api, fees: Vec<FeeUnverified>) -> Result<Vec<Fee>, ContractError> {
+    let mut total_bps = Uint64::zero();
+    let mut checked: Vec<Fee> = vec![];
+    for fee in fees {
+        if checked.iter().any(|other| other.label == fee.label) {
+            return Err(ContractError::DuplicateFeeLabel { label: fee.label });
+        }
+        total_bps += fee.bps;
+        checked.push(Fee {
+            bps: fee.bps,
+            address: api.addr_validate(&fee.address)?,
+            label: fee.label,
+        });
+    }
+    if total_bps > Uint64::new(10000) {
+        return Err(ContractError::FeeBpsTooHigh {});
+    }
+    Ok(checked)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateNftAuction { auction, admin } => {
+            create_nft_auction(deps, info, auction, admin)
+        }
+        ExecuteMsg::CreateDutchAuction { vault } => create_dutch_auction(deps, info, vault),
+        ExecuteMsg::UpdateConfig {
+            admin,
+            nft_auction_code_id,
+            vault_code_id,
+            fees,
+        } => update_config(deps, info, admin, nft_auction_code_id, vault_code_id, fees),
+    }
+}
+
+/// errors if an auction fee uses a label reserved for a factory fee
+fn ensure_labels_not_reserved<'a>(
+    config: &Config,
+    mut labels: impl Iterator<Item = &'a String>,
+) -> Result<(), ContractError> {
+    match labels.find(|label| config.fees.iter().any(|fee| fee.label == **label)) {
+        Some(label) => Err(ContractError::FeeLabelReserved {
+            label: label.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn next_auction_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let id = NEXT_AUCTION_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_AUCTION_ID.save(storage, &(id + 1))?;
+    Ok(id)
+}
+
+fn create_nft_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut auction: NftAuctionUnverified,
+    admin: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if deps.api.addr_validate(&auction.nft.owner)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    ensure_labels_not_reserved(&config, auction.fees.iter().map(|fee| &fee.label))?;
+    auction
+        .fees
+        .extend(config.fees.iter().map(|fee| NftFeeUnverified {
+            bps: fee.bps,
+            address: fee.address.to_string(),
+            label: fee.label.clone(),
+        }));
+
+    let pending = PendingAuction {
+        id: next_auction_id(deps.storage)?,
+        kind: AuctionKind::Nft {},
+        seller: info.sender.clone(),
+        collection: Some(deps.api.addr_validate(&auction.nft.contract_addr)?),
+        initial_end_time: Some(auction.end_time),
+    };
+    instantiate_auction(
+        deps.storage,
+        &config,
+        pending,
+        config.nft_auction_code_id,
+        to_binary(&NftAuctionInstantiateMsg {
+            auction: Some(auction),
+            // the seller pauses their own auction
+            admin: admin.or_else(|| Some(info.sender.to_string())),
+        })?,
+    )
+}
+
+fn create_dutch_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut vault: VaultInstantiateMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if let Some(seller_address) = &vault.seller_address {
+        if deps.api.addr_validate(seller_address)? != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+    ensure_labels_not_reserved(&config, vault.fees.iter().map(|fee| &fee.label))?;
+    vault
+        .fees
+        .extend(config.fees.iter().map(|fee| VaultFeeUnverified {
+            bps: fee.bps,
+            address: fee.address.to_string(),
+            label: fee.label.clone(),
+        }));
+    vault.owner = vault.owner.or_else(|| Some(info.sender.to_string()));
+
+    let collection = vault
+        .seller_nft
+        .as_ref()
+        .map(|nft| deps.api.addr_validate(&nft.contract_addr))
+        .transpose()?;
+    let pending = PendingAuction {
+        id: next_auction_id(deps.storage)?,
+        kind: AuctionKind::Dutch {},
+        seller: info.sender,
+        collection,
+        initial_end_time: vault.end_time,
+    };
+    instantiate_auction(
+        deps.storage,
+        &config,
+        pending,
+        config.vault_code_id,
+        to_binary(&vault)?,
+    )
+}
+
+/// instantiates the auction, registering it once the reply carries its address
+fn instantiate_auction(
+    storage: &mut dyn Storage,
+    config: &Config,
+    pending: PendingAuction,
+    code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    PENDING_AUCTION.save(storage, &pending)?;
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(config.admin.to_string()),
+        code_id,
+        msg,
+        funds: vec![],
+        label: format!("{}_{}", pending.kind.key(), pending.id),
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(
+            instantiate_msg,
+            INSTANTIATE_AUCTION_REPLY_ID,
+        ))
+        .add_attribute("method", "create_auction")
+        .add_attribute("kind", pending.kind.key())
+        .add_attribute("auction_id", pending.id.to_string())
+        .add_attribute("seller", pending.seller))
+}
+
+fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Option<String>,
+    nft_auction_code_id: Option<u64>,
+    vault_code_id: Option<u64>,
+    fees: Option<Vec<FeeUnverified>>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(admin) = admin {
+        config.admin = deps.api.addr_validate(&admin)?;
+    }
+    if let Some(nft_auction_code_id) = nft_auction_code_id {
+        config.nft_auction_code_id = nft_auction_code_id;
+    }
+    if let Some(vault_code_id) = vault_code_id {
+        config.vault_code_id = vault_code_id;
+    }
+    if let Some(fees) = fees {
+        config.fees = check_fees(deps.api, fees)?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_config")
+        .add_attribute("admin", config.admin))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != INSTANTIATE_AUCTION_REPLY_ID {
+        return Err(ContractError::UnknownReplyId { id: msg.id });
+    }
+    let events = match &msg.result {
+        SubMsgResult::Ok(response) => response.events.clone(),
+        SubMsgResult::Err(_) => vec![],
+    };
+    let instantiated = parse_reply_instantiate_data(msg)?;
+    let address = deps.api.addr_validate(&instantiated.contract_address)?;
+
+    let pending = PENDING_AUCTION.load(deps.storage)?;
+    let listing_id = match pending.kind {
+        AuctionKind::Nft {} => {
+            Some(listing_id(&events, &address).ok_or(ContractError::MissingListingId {})?)
+        }
+        AuctionKind::Dutch {} => None,
+    };
+    let record = pending.into_record(address, listing_id, env.block.time);
+    PENDING_AUCTION.remove(deps.storage);
+    auctions().save(deps.storage, &record.address, &record)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "register_auction")
+        .add_attribute("auction_id", record.id.to_string())
+        .add_attribute("address", record.address))
+}
+
+/// the listing id the nft-auction at `address` emitted when it was instantiated
+fn listing_id(events: &[Event], address: &Addr) -> Option<u64> {
+    events
+        .iter()
+        .filter(|event| {
+            event.ty == "wasm"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "_contract_address" && attr.value == address.as_str())
+        })
+        .flat_map(|event| event.attributes.iter())
+        .find(|attr| attr.key == "auction_id")
+        .and_then(|attr| attr.value.parse().ok())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::Auction { address } => to_binary(&query_auction(deps, env, address)?),
+        QueryMsg::Auctions { start_after, limit } => {
+            to_binary(&query_auctions(deps, start_after, limit)?)
+        }
+        QueryMsg::AuctionsBySeller {
+            seller,
+            start_after,
+            limit,
+        } => to_binary(&query_auctions_by_seller(deps, seller, start_after, limit)?),
+        QueryMsg::AuctionsByCollection {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&query_auctions_by_collection(
+            deps,
+            collection,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AuctionsByStatus {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_auctions_by_status(
+            deps,
+            env,
+            status,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AuctionsByEndTime {
+            ends_before,
+            start_after,
+            limit,
+        } => to_binary(&query_auctions_by_end_time(
+            deps,
+            ends_before,
+            start_after,
+            limit,
+        )?),
+    }
+}
+
+/// reads the status from the auction contract itself, as neither kind of
+/// auction reports back to the factory
+fn auction_status(deps: Deps, env: &Env, auction: &AuctionRecord) -> StdResult<AuctionStatus> {
+    match auction.kind {
+        AuctionKind::Nft {} => {
+            let auction_id = auction
+                .listing_id
+                .ok_or_else(|| StdError::not_found("nft-auction listing id"))?;
+            let AuctionInfoResponse { auction_info, .. } = deps.querier.query_wasm_smart(
+                &auction.address,
+                &NftAuctionQueryMsg::AuctionInfo { auction_id },
+            )?;
+            if auction_info.is_settled() {
+                Ok(AuctionStatus::Ended {})
+            } else if env.block.time < auction_info.start_time {
+                Ok(AuctionStatus::Upcoming {})
+            } else if env.block.time < auction_info.end_time {
+                Ok(AuctionStatus::Active {})
+            } else {
+                Ok(AuctionStatus::Ended {})
+            }
+        }
+        AuctionKind::Dutch {} => {
+            let market_state: MarketStateResponse = deps
+                .querier
+                .query_wasm_smart(&auction.address, &VaultQueryMsg::GetMarketState {})?;
+            Ok(match market_state.phase {
+                Phase::NotScheduled {} | Phase::Scheduled {} => AuctionStatus::Upcoming {},
+                Phase::Open {} => AuctionStatus::Active {},
+                Phase::Closed {} => AuctionStatus::Ended {},
+            })
+        }
+    }
+}
+
+fn query_auction(deps: Deps, env: Env, address: String) -> StdResult<AuctionResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let auction = auctions().load(deps.storage, &address)?;
+    let status = auction_status(deps, &env, &auction)?;
+    Ok(AuctionResponse { auction, status })
+}
+
+fn validate_start_after(deps: Deps, start_after: Option<String>) -> StdResult<Option<Addr>> {
+    start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()
+}
+
+fn collect_auctions(
+    auctions: impl Iterator<Item = StdResult<(Addr, AuctionRecord)>>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let auctions = auctions
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction))
+        .collect::<StdResult<Vec<AuctionRecord>>>()?;
+    Ok(AuctionsResponse { auctions })
+}
+
+fn query_auctions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let start_after = validate_start_after(deps, start_after)?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    collect_auctions(
+        auctions().range(deps.storage, start, None, Order::Ascending),
+        limit,
+    )
+}
+
+fn query_auctions_by_seller(
+    deps: Deps,
+    seller: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let seller = deps.api.addr_validate(&seller)?;
+    let start_after = validate_start_after(deps, start_after)?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    collect_auctions(
+        auctions()
+            .idx
+            .seller
+            .prefix(seller)
+            .range(deps.storage, start, None, Order::Ascending),
+        limit,
+    )
+}
+
+fn query_auctions_by_collection(
+    deps: Deps,
+    collection: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let collection = deps.api.addr_validate(&collection)?;
+    let start_after = validate_start_after(deps, start_after)?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    collect_auctions(
+        auctions()
+            .idx
+            .collection
+            .prefix(collection.to_string())
+            .range(deps.storage, start, None, Order::Ascending),
+        limit,
+    )
+}
+
+fn query_auctions_by_status(
+    deps: Deps,
+    env: Env,
+    status: AuctionStatus,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsByStatusResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = validate_start_after(deps, start_after)?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    // every auction scanned costs a query to it, so the scan is bounded by
+    // the limit rather than by the matches found
+    let scanned = auctions()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, auction)| auction))
+        .collect::<StdResult<Vec<AuctionRecord>>>()?;
+    let next_start_after = match scanned.last() {
+        Some(last) if scanned.len() == limit => Some(last.address.clone()),
+        _ => None,
+    };
+
+    let mut matching = vec![];
+    for auction in scanned {
+        if auction_status(deps, &env, &auction)? == status {
+            matching.push(auction);
+        }
+    }
+    Ok(AuctionsByStatusResponse {
+        auctions: matching,
+        next_start_after,
+    })
+}
+
+fn query_auctions_by_end_time(
+    deps: Deps,
+    ends_before: Option<Uint64>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AuctionsResponse> {
+    let start_after = validate_start_after(deps, start_after)?;
+    let start = match &start_after {
+        Some(address) => {
+            let auction = auctions().load(deps.storage, address)?;
+            Some(Bound::exclusive((auction.initial_end_key(), address)))
+        }
+        None => None,
+    };
+    let ending = auctions()
+        .idx
+        .initial_end_time
+        .range(deps.storage, start, None, Order::Ascending)
+        .take_while(|item| match (item, ends_before) {
+            (Ok((_, auction)), Some(ends_before)) => auction.initial_end_key() < ends_before.u64(),
+            _ => true,
+        });
+    collect_auctions(ending, limit)
+}
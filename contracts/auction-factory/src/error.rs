@@ -0,0 +1,30 @@
+use cosmwasm_std::StdError;
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Factory fees add up to more than 10000 basis points")]
+    FeeBpsTooHigh {},
+
+    #[error("Fee label {label} is used more than once")]
+    DuplicateFeeLabel { label: String },
+
+    #[error("Fee label {label} is reserved for a factory fee")]
+    FeeLabelReserved { label: String },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("The nft-auction did not report the id of its listing")]
+    MissingListingId {},
+}
@@ -0,0 +1,127 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint64};
+
+use crate::state::{AuctionRecord, Config};
+
+#[cw_serde]
+pub struct FeeUnverified {
+    pub bps: Uint64,
+    pub address: String,
+    pub label: String,
+}
+
+/// Message type for `instantiate` entry_point
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// updates the config and is the wasm admin of every auction created,
+    /// defaults to the instantiating address
+    pub admin: Option<String>,
+    pub nft_auction_code_id: u64,
+    pub vault_code_id: u64,
+    /// added to the fees of every auction the factory creates
+    #[serde(default)]
+    pub fees: Vec<FeeUnverified>,
+}
+
+/// Message type for `execute` entry_point
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Instantiates an nft-auction listing an NFT owned by the sender
+    CreateNftAuction {
+        auction: nft_auction::msg::AuctionUnverified,
+        /// the address allowed to pause the auction, defaults to the sender
+        admin: Option<String>,
+    },
+    /// Instantiates a dutch-auction-vault selling on the sender's behalf
+    CreateDutchAuction {
+        vault: dutch_auction_vault::msg::InstantiateMsg,
+    },
+    /// Updates the factory config, callable by the admin
+    UpdateConfig {
+        admin: Option<String>,
+        nft_auction_code_id: Option<u64>,
+        vault_code_id: Option<u64>,
+        fees: Option<Vec<FeeUnverified>>,
+    },
+}
+
+/// Where an auction stands, read from the auction contract
+#[cw_serde]
+pub enum AuctionStatus {
+    /// not scheduled or not started yet
+    Upcoming {},
+    /// taking bids or purchases
+    Active {},
+    Ended {},
+}
+
+/// Message type for `query` entry_point
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Config)]
+    Config {},
+    /// Returns an auction created by the factory along with its status
+    #[returns(AuctionResponse)]
+    Auction { address: String },
+    /// Lists every auction by address
+    #[returns(AuctionsResponse)]
+    Auctions {
+        /// the auction address to start after
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AuctionsResponse)]
+    AuctionsBySeller {
+        seller: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AuctionsResponse)]
+    AuctionsByCollection {
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the auctions with `status`, querying each auction for it. Scans
+    /// at most `limit` auctions by address per call, so a page can hold fewer
+    /// than `limit` matches, or none, with more to come after `next_start_after`
+    #[returns(AuctionsByStatusResponse)]
+    AuctionsByStatus {
+        status: AuctionStatus,
+        start_after: Option<String>,
+        /// the most auctions to scan
+        limit: Option<u32>,
+    },
+    /// Lists auctions by the end time they were created with, soonest first.
+    /// auctions without a set end time, such as vaults that close once their
+    /// duration has run, come last. the index is not updated when bidding
+    /// extensions or a buy-now purchase move an auction's end, so query the
+    /// auction itself for when it actually ends
+    #[returns(AuctionsResponse)]
+    AuctionsByEndTime {
+        /// only lists auctions initially ending before this unix time in seconds
+        ends_before: Option<Uint64>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct AuctionResponse {
+    pub auction: AuctionRecord,
+    pub status: AuctionStatus,
+}
+
+#[cw_serde]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionRecord>,
+}
+
+#[cw_serde]
+pub struct AuctionsByStatusResponse {
+    pub auctions: Vec<AuctionRecord>,
+    /// the last auction scanned, to continue from. unset once every auction
+    /// has been scanned
+    pub next_start_after: Option<Addr>,
+}
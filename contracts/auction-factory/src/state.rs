@@ -0,0 +1,141 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Timestamp, Uint64};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+
+#[cw_serde]
+pub struct Config {
+    /// updates the config and is the wasm admin of every auction created
+    pub admin: Addr,
+    pub nft_auction_code_id: u64,
+    pub vault_code_id: u64,
+    /// added to the fees of every auction the factory creates
+    pub fees: Vec<Fee>,
+}
+
+#[cw_serde]
+pub struct Fee {
+    pub bps: Uint64,
+    pub address: Addr,
+    pub label: String,
+}
+
+#[cw_serde]
+pub enum AuctionKind {
+    /// an nft-auction contract
+    Nft {},
+    /// a dutch-auction-vault contract
+    Dutch {},
+}
+
+impl AuctionKind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            AuctionKind::Nft {} => "nft_auction",
+            AuctionKind::Dutch {} => "dutch_auction",
+        }
+    }
+}
+
+/// an auction created by the factory
+#[cw_serde]
+pub struct AuctionRecord {
+    pub id: u64,
+    pub address: Addr,
+    pub kind: AuctionKind,
+    pub seller: Addr,
+    /// the NFT contract being auctioned, if any
+    pub collection: Option<Addr>,
+    /// unix time in seconds at which the auction was set to end when created,
+    /// None if it ends once its duration has passed since it was scheduled.
+    /// not updated when bidding extensions or a buy-now purchase move the end
+    pub initial_end_time: Option<Uint64>,
+    /// the id of the listing inside an nft-auction contract, None for a dutch auction
+    pub listing_id: Option<u64>,
+    pub created_at: Timestamp,
+}
+
+/// an auction waiting for its instantiation reply
+#[cw_serde]
+pub struct PendingAuction {
+    pub id: u64,
+    pub kind: AuctionKind,
+    pub seller: Addr,
+    pub collection: Option<Addr>,
+    pub initial_end_time: Option<Uint64>,
+}
+
+impl PendingAuction {
+    pub fn into_record(
+        self,
+        address: Addr,
+        listing_id: Option<u64>,
+        created_at: Timestamp,
+    ) -> AuctionRecord {
+        AuctionRecord {
+            id: self.id,
+            address,
+            kind: self.kind,
+            seller: self.seller,
+            collection: self.collection,
+            initial_end_time: self.initial_end_time,
+            listing_id,
+            created_at,
+        }
+    }
+}
+
+impl AuctionRecord {
+    /// the key of the initial end time index, auctions without a set end sort last
+    pub fn initial_end_key(&self) -> u64 {
+        self.initial_end_time
+            .map_or(u64::MAX, |end_time| end_time.u64())
+    }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_AUCTION: Item<PendingAuction> = Item::new("pending_auction");
+/// the id assigned to the next auction created
+pub const NEXT_AUCTION_ID: Item<u64> = Item::new("next_auction_id");
+
+pub struct AuctionIndexes<'a> {
+    pub seller: MultiIndex<'a, Addr, AuctionRecord, &'a Addr>,
+    /// empty for auctions that do not sell an NFT
+    pub collection: MultiIndex<'a, String, AuctionRecord, &'a Addr>,
+    pub initial_end_time: MultiIndex<'a, u64, AuctionRecord, &'a Addr>,
+}
+
+impl<'a> IndexList<AuctionRecord> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<AuctionRecord>> + '_> {
+        let v: Vec<&dyn Index<AuctionRecord>> =
+            vec![&self.seller, &self.collection, &self.initial_end_time];
+        Box::new(v.into_iter())
+    }
+}
+
+/// every auction by address, indexed by seller, collection and initial end time
+pub fn auctions<'a>() -> IndexedMap<'a, &'a Addr, AuctionRecord, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        seller: MultiIndex::new(
+            |_pk, auction| auction.seller.clone(),
+            "auctions",
+            "auctions__seller",
+        ),
+        collection: MultiIndex::new(
+            |_pk, auction| {
+                auction
+                    .collection
+                    .as_ref()
+                    .map(Addr::to_string)
+                    .unwrap_or_default()
+            },
+            "auctions",
+            "auctions__collection",
+        ),
+        initial_end_time: MultiIndex::new(
+            |_pk, auction| auction.initial_end_key(),
+            "auctions",
+            "auctions__initial_end_time",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
@@ -0,0 +1,643 @@
+use std::str::FromStr;
+
+use cosmwasm_std::{to_binary, Addr, Decimal, Empty, Uint128, Uint64};
+use cw721::Cw721ReceiveMsg;
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use dutch_auction_vault::fungible::FungibleAsset;
+use dutch_auction_vault::msg::UncheckedNft;
+use nft_auction::auction::{DynamicBiddingExtension, Fee as NftFee};
+use nft_auction::msg::{AuctionUnverified, NftUnverified};
+
+use crate::contract::{execute, instantiate, query, reply};
+use crate::msg::{
+    AuctionResponse, AuctionStatus, AuctionsByStatusResponse, AuctionsResponse, ExecuteMsg,
+    FeeUnverified, InstantiateMsg, QueryMsg,
+};
+use crate::state::{AuctionKind, Config, Fee};
+use crate::ContractError;
+
+pub fn auction_factory_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+    Box::new(contract)
+}
+
+pub fn nft_auction_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        nft_auction::contract::execute,
+        nft_auction::contract::instantiate,
+        nft_auction::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn dutch_auction_vault_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        dutch_auction_vault::contract::execute,
+        dutch_auction_vault::contract::instantiate,
+        dutch_auction_vault::contract::query,
+    );
+    Box::new(contract)
+}
+
+struct TestEnv {
+    app: App,
+    factory_addr: Addr,
+    seller: Addr,
+    treasury: Addr,
+}
+
+impl TestEnv {
+    fn setup() -> TestEnv {
+        let admin = Addr::unchecked("admin");
+        let seller = Addr::unchecked("seller");
+        let treasury = Addr::unchecked("treasury");
+
+        let mut app = App::default();
+        let factory_id = app.store_code(auction_factory_contract());
+        let nft_auction_code_id = app.store_code(nft_auction_contract());
+        let vault_code_id = app.store_code(dutch_auction_vault_contract());
+
+        let factory_addr = app
+            .instantiate_contract(
+                factory_id,
+                admin,
+                &InstantiateMsg {
+                    admin: None,
+                    nft_auction_code_id,
+                    vault_code_id,
+                    fees: vec![FeeUnverified {
+                        bps: Uint64::new(250),
+                        address: treasury.to_string(),
+                        label: "factory".to_string(),
+                    }],
+                },
+                &[],
+                "auction_factory".to_string(),
+                None,
+            )
+            .unwrap();
+
+        TestEnv {
+            app,
+            factory_addr,
+            seller,
+            treasury,
+        }
+    }
+
+    fn nft_auction_msg(&self, token_id: &str, end_time: u64) -> AuctionUnverified {
+        AuctionUnverified {
+            nft: NftUnverified {
+                token_id: token_id.to_string(),
+                owner: self.seller.to_string(),
+                contract_addr: "collection_a".to_string(),
+            },
+            start_time: Uint64::new(self.app.block_info().time.seconds()),
+            end_time: Uint64::new(end_time),
+            reserve_price: Uint64::new(100),
+            dynamic_bidding_extension: DynamicBiddingExtension::new(
+                false,
+                Uint64::zero(),
+                Uint128::new(1),
+            ),
+            denom: "ujuno".to_string(),
+            fees: vec![],
+            payout_address: self.seller.to_string(),
+            buy_now_price: None,
+        }
+    }
+
+    fn vault_msg(&self) -> dutch_auction_vault::msg::InstantiateMsg {
+        dutch_auction_vault::msg::InstantiateMsg {
+            start_price: Decimal::from_str("1.0").unwrap(),
+            min_price: Decimal::from_str("0.1").unwrap(),
+            target_duration: Uint128::new(100),
+            spend_asset: FungibleAsset::Native {
+                denom: "ujuno".to_string(),
+            },
+            unit_asset: FungibleAsset::Native {
+                denom: "utoken".to_string(),
+            },
+            seller_address: None,
+            seller_nft: Some(UncheckedNft {
+                contract_addr: "collection_b".to_string(),
+                token_id: "1".to_string(),
+            }),
+            price_curve: None,
+            end_time: None,
+            fees: vec![],
+            referral_bps: None,
+            validator_share_bps: None,
+            settlement: None,
+            vesting: None,
+            epoch_release: None,
+            max_units_per_buyer: None,
+            max_units_per_tx: None,
+            min_units_per_tx: None,
+            min_total_units: None,
+            sale_phases: vec![],
+            owner: None,
+            dao: None,
+        }
+    }
+
+    fn auctions(&self, msg: &QueryMsg) -> Vec<Addr> {
+        let response: AuctionsResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(&self.factory_addr, msg)
+            .unwrap();
+        response
+            .auctions
+            .into_iter()
+            .map(|auction| auction.address)
+            .collect()
+    }
+}
+
+/// the address of the auction instantiated by a factory response
+fn created_address(response: &cw_multi_test::AppResponse) -> Addr {
+    let event = response
+        .events
+        .iter()
+        .find(|event| {
+            event
+                .attributes
+                .iter()
+                .any(|a| a.key == "method" && a.value == "register_auction")
+        })
+        .unwrap();
+    Addr::unchecked(
+        &event
+            .attributes
+            .iter()
+            .find(|a| a.key == "address")
+            .unwrap()
+            .value,
+    )
+}
+
+#[test]
+fn test_create_auctions() {
+    let mut env = TestEnv::setup();
+    let end_time = env.app.block_info().time.seconds() + 1_000;
+
+    let mut auction = env.nft_auction_msg("1", end_time);
+    auction.fees = vec![nft_auction::msg::FeeUnverified {
+        bps: Uint64::new(500),
+        address: "artist".to_string(),
+        label: "royalty".to_string(),
+    }];
+    let response = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateNftAuction {
+                auction,
+                admin: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let nft_auction_addr = created_address(&response);
+
+    // the factory fee is appended to the seller's own fees
+    let info: nft_auction::msg::AuctionInfoResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &nft_auction_addr,
+            &nft_auction::msg::QueryMsg::AuctionInfo { auction_id: 0 },
+        )
+        .unwrap();
+    assert_eq!(
+        info.auction_info.fees,
+        vec![
+            NftFee::new(
+                Uint64::new(500),
+                Addr::unchecked("artist"),
+                "royalty".to_string()
+            ),
+            NftFee::new(
+                Uint64::new(250),
+                env.treasury.clone(),
+                "factory".to_string()
+            ),
+        ]
+    );
+
+    let response = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateDutchAuction {
+                vault: env.vault_msg(),
+            },
+            &[],
+        )
+        .unwrap();
+    let vault_addr = created_address(&response);
+
+    let market_state: dutch_auction_vault::msg::MarketStateResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &vault_addr,
+            &dutch_auction_vault::msg::QueryMsg::GetMarketState {},
+        )
+        .unwrap();
+    assert_eq!(market_state.config.fees.len(), 1);
+    assert_eq!(market_state.config.fees[0].address, env.treasury);
+
+    let response: AuctionResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &env.factory_addr,
+            &QueryMsg::Auction {
+                address: vault_addr.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.auction.id, 1);
+    assert_eq!(response.auction.kind, AuctionKind::Dutch {});
+    assert_eq!(response.auction.listing_id, None);
+    assert_eq!(response.auction.seller, env.seller);
+    assert_eq!(
+        response.auction.collection,
+        Some(Addr::unchecked("collection_b"))
+    );
+    assert_eq!(response.status, AuctionStatus::Upcoming {});
+
+    let response: AuctionResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &env.factory_addr,
+            &QueryMsg::Auction {
+                address: nft_auction_addr.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(response.auction.id, 0);
+    // the status is read from the listing the nft-auction reported
+    assert_eq!(response.auction.listing_id, Some(0));
+    assert_eq!(
+        response.auction.initial_end_time,
+        Some(Uint64::new(end_time))
+    );
+    assert_eq!(response.status, AuctionStatus::Active {});
+}
+
+#[test]
+fn test_nft_payload_keeps_factory_fees() {
+    let mut env = TestEnv::setup();
+    let end_time = env.app.block_info().time.seconds() + 1_000;
+    let response = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateNftAuction {
+                auction: env.nft_auction_msg("1", end_time),
+                admin: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let nft_auction_addr = created_address(&response);
+
+    // the seller configures the listing while sending the NFT, without the
+    // factory fee in the payload
+    let payload = nft_auction::msg::ReceiveMsg::CreateAuction {
+        start_time: Uint64::new(env.app.block_info().time.seconds()),
+        end_time: Uint64::new(end_time + 1_000),
+        reserve_price: Uint64::new(200),
+        dynamic_bidding_extension: DynamicBiddingExtension::new(
+            false,
+            Uint64::zero(),
+            Uint128::new(1),
+        ),
+        denom: "ujuno".to_string(),
+        fees: vec![nft_auction::msg::FeeUnverified {
+            bps: Uint64::new(500),
+            address: "artist".to_string(),
+            label: "royalty".to_string(),
+        }],
+        payout_address: None,
+        buy_now_price: None,
+    };
+    env.app
+        .execute_contract(
+            Addr::unchecked("collection_a"),
+            nft_auction_addr.clone(),
+            &nft_auction::msg::ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: env.seller.to_string(),
+                token_id: "1".to_string(),
+                msg: to_binary(&payload).unwrap(),
+            }),
+            &[],
+        )
+        .unwrap();
+
+    let info: nft_auction::msg::AuctionInfoResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &nft_auction_addr,
+            &nft_auction::msg::QueryMsg::AuctionInfo { auction_id: 0 },
+        )
+        .unwrap();
+    assert!(info.auction_info.nft.received);
+    assert_eq!(info.auction_info.reserve_price, Uint128::new(200));
+    assert_eq!(
+        info.auction_info.fees,
+        vec![
+            NftFee::new(
+                Uint64::new(250),
+                env.treasury.clone(),
+                "factory".to_string()
+            ),
+            NftFee::new(
+                Uint64::new(500),
+                Addr::unchecked("artist"),
+                "royalty".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_create_auction_rejects_invalid_requests() {
+    let mut env = TestEnv::setup();
+    let end_time = env.app.block_info().time.seconds() + 1_000;
+
+    // only the NFT owner can auction it
+    let err = env
+        .app
+        .execute_contract(
+            Addr::unchecked("stranger"),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateNftAuction {
+                auction: env.nft_auction_msg("1", end_time),
+                admin: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    let mut vault = env.vault_msg();
+    vault.seller_address = Some(env.seller.to_string());
+    vault.seller_nft = None;
+    let err = env
+        .app
+        .execute_contract(
+            Addr::unchecked("stranger"),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateDutchAuction { vault },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+
+    // sellers cannot claim the factory's fee label
+    let mut vault = env.vault_msg();
+    vault.fees = vec![dutch_auction_vault::msg::FeeUnverified {
+        bps: Uint64::new(100),
+        address: env.seller.to_string(),
+        label: "factory".to_string(),
+    }];
+    let err = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateDutchAuction { vault },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::FeeLabelReserved {
+            label: "factory".to_string()
+        }
+    );
+
+    // only the admin updates the config
+    let err = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.factory_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                nft_auction_code_id: None,
+                vault_code_id: None,
+                fees: Some(vec![]),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized {}
+    );
+    let err = env
+        .app
+        .execute_contract(
+            Addr::unchecked("admin"),
+            env.factory_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                nft_auction_code_id: None,
+                vault_code_id: None,
+                fees: Some(vec![FeeUnverified {
+                    bps: Uint64::new(10001),
+                    address: env.treasury.to_string(),
+                    label: "factory".to_string(),
+                }]),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::FeeBpsTooHigh {}
+    );
+
+    env.app
+        .execute_contract(
+            Addr::unchecked("admin"),
+            env.factory_addr.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                nft_auction_code_id: None,
+                vault_code_id: None,
+                fees: Some(vec![]),
+            },
+            &[],
+        )
+        .unwrap();
+    let config: Config = env
+        .app
+        .wrap()
+        .query_wasm_smart(&env.factory_addr, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.fees, Vec::<Fee>::new());
+}
+
+#[test]
+fn test_query_auctions() {
+    let mut env = TestEnv::setup();
+    let now = env.app.block_info().time.seconds();
+
+    let mut created = vec![];
+    for (token_id, end_time) in [("1", now + 3_000), ("2", now + 1_000), ("3", now + 2_000)] {
+        let response = env
+            .app
+            .execute_contract(
+                env.seller.clone(),
+                env.factory_addr.clone(),
+                &ExecuteMsg::CreateNftAuction {
+                    auction: env.nft_auction_msg(token_id, end_time),
+                    admin: None,
+                },
+                &[],
+            )
+            .unwrap();
+        created.push(created_address(&response));
+    }
+    let response = env
+        .app
+        .execute_contract(
+            Addr::unchecked("other_seller"),
+            env.factory_addr.clone(),
+            &ExecuteMsg::CreateDutchAuction {
+                vault: env.vault_msg(),
+            },
+            &[],
+        )
+        .unwrap();
+    let vault_addr = created_address(&response);
+
+    let mut all = created.clone();
+    all.push(vault_addr.clone());
+    all.sort();
+    assert_eq!(
+        env.auctions(&QueryMsg::Auctions {
+            start_after: None,
+            limit: None,
+        }),
+        all
+    );
+    assert_eq!(
+        env.auctions(&QueryMsg::Auctions {
+            start_after: Some(all[1].to_string()),
+            limit: Some(1),
+        }),
+        vec![all[2].clone()]
+    );
+
+    let mut by_seller = created.clone();
+    by_seller.sort();
+    assert_eq!(
+        env.auctions(&QueryMsg::AuctionsBySeller {
+            seller: env.seller.to_string(),
+            start_after: None,
+            limit: None,
+        }),
+        by_seller
+    );
+    assert_eq!(
+        env.auctions(&QueryMsg::AuctionsByCollection {
+            collection: "collection_b".to_string(),
+            start_after: None,
+            limit: None,
+        }),
+        vec![vault_addr.clone()]
+    );
+
+    // soonest first, with the vault's open end last
+    assert_eq!(
+        env.auctions(&QueryMsg::AuctionsByEndTime {
+            ends_before: None,
+            start_after: None,
+            limit: None,
+        }),
+        vec![
+            created[1].clone(),
+            created[2].clone(),
+            created[0].clone(),
+            vault_addr.clone(),
+        ]
+    );
+    assert_eq!(
+        env.auctions(&QueryMsg::AuctionsByEndTime {
+            ends_before: Some(Uint64::new(now + 3_000)),
+            start_after: Some(created[1].to_string()),
+            limit: None,
+        }),
+        vec![created[2].clone()]
+    );
+
+    env.app.update_block(|block| {
+        block.time = block.time.plus_seconds(1_500);
+    });
+    let by_status = |env: &TestEnv, status: AuctionStatus, start_after: Option<&Addr>, limit| {
+        let response: AuctionsByStatusResponse = env
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &env.factory_addr,
+                &QueryMsg::AuctionsByStatus {
+                    status,
+                    start_after: start_after.map(Addr::to_string),
+                    limit,
+                },
+            )
+            .unwrap();
+        let addresses = response
+            .auctions
+            .into_iter()
+            .map(|auction| auction.address)
+            .collect::<Vec<Addr>>();
+        (addresses, response.next_start_after)
+    };
+    assert_eq!(
+        by_status(&env, AuctionStatus::Ended {}, None, None),
+        (vec![created[1].clone()], None)
+    );
+    assert_eq!(
+        by_status(&env, AuctionStatus::Upcoming {}, None, None),
+        (vec![vault_addr.clone()], None)
+    );
+
+    // each page scans at most limit auctions, matching or not
+    let expected_upcoming = |page: &[Addr]| {
+        page.iter()
+            .filter(|address| **address == vault_addr)
+            .cloned()
+            .collect::<Vec<Addr>>()
+    };
+    assert_eq!(
+        by_status(&env, AuctionStatus::Upcoming {}, None, Some(2)),
+        (expected_upcoming(&all[..2]), Some(all[1].clone()))
+    );
+    assert_eq!(
+        by_status(&env, AuctionStatus::Upcoming {}, Some(&all[1]), Some(2)),
+        (expected_upcoming(&all[2..]), Some(all[3].clone()))
+    );
+    assert_eq!(
+        by_status(&env, AuctionStatus::Upcoming {}, Some(&all[3]), Some(2)),
+        (vec![], None)
+    );
+}
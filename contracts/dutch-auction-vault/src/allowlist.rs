@@ -0,0 +1,218 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128, Uint64};
+use sha2::{Digest, Sha256};
+
+use crate::ContractError;
+
+/// the most sale phases a market can be configured with
+pub const MAX_SALE_PHASES: usize = 10;
+/// prefixes a leaf preimage, so a leaf can never be taken for a node
+const LEAF_PREFIX: u8 = 0x00;
+/// prefixes a node preimage
+const NODE_PREFIX: u8 = 0x01;
+
+/// A window of the sale that opens at `start_time` and lasts until the next
+/// phase opens. A phase with a merkle root only sells to allowlisted addresses
+#[cw_serde]
+pub struct SalePhase {
+    pub name: String,
+    /// unix time in seconds at which the phase opens
+    pub start_time: Uint64,
+    /// hex encoded root of a sha256 merkle tree with one leaf per allowed
+    /// address and its allocation, see `leaf_hash` and `hash_pair`.
+    /// None for a public phase
+    pub merkle_root: Option<String>,
+}
+
+/// proves a buyer is allowed `allocation` units in a gated phase
+#[cw_serde]
+pub struct AllowlistProof {
+    /// the units allocated to the buyer in the leaf
+    pub allocation: Uint128,
+    /// hex encoded sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+}
+
+impl SalePhase {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if let Some(merkle_root) = &self.merkle_root {
+            decode_hash(merkle_root).map_err(|_e| ContractError::InvalidMerkleRoot {})?;
+        }
+        Ok(())
+    }
+}
+
+/// ensures there are few enough phases and that each opens after the last
+pub fn validate_sale_phases(phases: &[SalePhase]) -> Result<(), ContractError> {
+    if phases.len() > MAX_SALE_PHASES {
+        return Err(ContractError::TooManySalePhases {});
+    }
+    for phase in phases {
+        phase.validate()?;
+    }
+    if phases
+        .windows(2)
+        .any(|pair| pair[0].start_time >= pair[1].start_time)
+    {
+        return Err(ContractError::SalePhasesOutOfOrder {});
+    }
+    Ok(())
+}
+
+/// the phase open at `seconds` along with its index, or None before the first opens
+pub fn active_phase(phases: &[SalePhase], seconds: Uint64) -> Option<(u64, &SalePhase)> {
+    phases
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, phase)| phase.start_time <= seconds)
+        .map(|(index, phase)| (index as u64, phase))
+}
+
+impl AllowlistProof {
+    /// whether the proof places `address` and its allocation under `merkle_root`
+    pub fn verify(&self, merkle_root: &str, address: &Addr) -> Result<bool, ContractError> {
+        let root = decode_hash(merkle_root).map_err(|_e| ContractError::InvalidMerkleRoot {})?;
+        let hash = self.proof.iter().try_fold(
+            leaf_hash(address, self.allocation),
+            |hash, sibling| -> Result<_, ContractError> {
+                let sibling =
+                    decode_hash(sibling).map_err(|_e| ContractError::InvalidMerkleProof {})?;
+                Ok(hash_pair(hash, sibling))
+            },
+        )?;
+        Ok(hash == root)
+    }
+}
+
+/// the leaf allowing `address` to buy `allocation` units. the address is
+/// length prefixed, so no address and allocation encode like another pair
+pub fn leaf_hash(address: &Addr, allocation: Uint128) -> [u8; 32] {
+    let address = address.as_bytes();
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update((address.len() as u32).to_be_bytes());
+    hasher.update(address);
+    hasher.update(allocation.u128().to_be_bytes());
+    hasher.finalize().into()
+}
+
+/// hashes two nodes in sorted order, so a proof does not need to say which
+/// side each sibling is on
+pub fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], hex::FromHexError> {
+    let mut decoded = [0u8; 32];
+    hex::decode_to_slice(hash, &mut decoded)?;
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phase(start_time: u64, merkle_root: Option<String>) -> SalePhase {
+        SalePhase {
+            name: format!("phase_{}", start_time),
+            start_time: Uint64::new(start_time),
+            merkle_root,
+        }
+    }
+
+    #[test]
+    fn test_validate_sale_phases() {
+        let root = hex::encode([1u8; 32]);
+        assert!(validate_sale_phases(&[phase(10, Some(root.clone())), phase(20, None)]).is_ok());
+        assert_eq!(
+            validate_sale_phases(&[phase(20, Some(root)), phase(20, None)]),
+            Err(ContractError::SalePhasesOutOfOrder {})
+        );
+        assert_eq!(
+            validate_sale_phases(&[phase(10, Some("not_hex".to_string()))]),
+            Err(ContractError::InvalidMerkleRoot {})
+        );
+
+        let too_many = (0..=MAX_SALE_PHASES as u64)
+            .map(|i| phase(i, None))
+            .collect::<Vec<SalePhase>>();
+        assert_eq!(
+            validate_sale_phases(&too_many),
+            Err(ContractError::TooManySalePhases {})
+        );
+    }
+
+    #[test]
+    fn test_active_phase() {
+        let phases = vec![phase(10, Some(hex::encode([1u8; 32]))), phase(20, None)];
+        assert_eq!(active_phase(&phases, Uint64::new(9)), None);
+        assert_eq!(
+            active_phase(&phases, Uint64::new(10)),
+            Some((0, &phases[0]))
+        );
+        assert_eq!(
+            active_phase(&phases, Uint64::new(19)),
+            Some((0, &phases[0]))
+        );
+        assert_eq!(
+            active_phase(&phases, Uint64::new(20)),
+            Some((1, &phases[1]))
+        );
+        assert_eq!(active_phase(&[], Uint64::new(20)), None);
+    }
+
+    #[test]
+    fn test_verify_proof() {
+        let alice = Addr::unchecked("alice");
+        let bob = Addr::unchecked("bob");
+        let carol = Addr::unchecked("carol");
+        let leaves = [
+            leaf_hash(&alice, Uint128::new(100)),
+            leaf_hash(&bob, Uint128::new(200)),
+            leaf_hash(&carol, Uint128::new(300)),
+        ];
+        // carol's leaf is paired with itself
+        let left = hash_pair(leaves[0], leaves[1]);
+        let right = hash_pair(leaves[2], leaves[2]);
+        let root = hex::encode(hash_pair(left, right));
+
+        let alice_proof = AllowlistProof {
+            allocation: Uint128::new(100),
+            proof: vec![hex::encode(leaves[1]), hex::encode(right)],
+        };
+        assert_eq!(alice_proof.verify(&root, &alice), Ok(true));
+        // the proof is bound to both the address and the allocation
+        assert_eq!(alice_proof.verify(&root, &bob), Ok(false));
+        let inflated = AllowlistProof {
+            allocation: Uint128::new(1000),
+            ..alice_proof.clone()
+        };
+        assert_eq!(inflated.verify(&root, &alice), Ok(false));
+        // an address ending in digits cannot borrow them from the allocation
+        assert_ne!(
+            leaf_hash(&Addr::unchecked("alice1"), Uint128::new(0)),
+            leaf_hash(&alice, Uint128::new(10))
+        );
+
+        let carol_proof = AllowlistProof {
+            allocation: Uint128::new(300),
+            proof: vec![hex::encode(leaves[2]), hex::encode(left)],
+        };
+        assert_eq!(carol_proof.verify(&root, &carol), Ok(true));
+
+        let malformed = AllowlistProof {
+            allocation: Uint128::new(100),
+            proof: vec!["zz".to_string()],
+        };
+        assert_eq!(
+            malformed.verify(&root, &alice),
+            Err(ContractError::InvalidMerkleProof {})
+        );
+    }
+}
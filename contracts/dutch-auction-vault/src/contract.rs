@@ -1,27 +1,60 @@
+use contract_upgrade::check_upgrade;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdError, StdResult, Uint128, Uint64,
+    from_binary, to_binary, wasm_execute, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, Reply, Response, StdError, StdResult, Storage, Uint128, Uint64,
 };
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
 use cw_utils::one_coin;
 
+use crate::allowlist::{active_phase, AllowlistProof};
+use crate::epoch::EpochRelease;
 use crate::error::ContractError;
+use crate::fee::{bps_of, Fee};
 use crate::fungible::{CheckedFungibleAsset, FungibleAsset};
+use crate::governance::{ensure_dao, is_dao, record_change, GovernanceChange, ParameterChange};
 use crate::market::{Cost, Market};
+use crate::migrations::{migrate_from_v0_1, v0_2};
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, MarketStateResponse, MigrateMsg, QueryMsg, ReceiveMsg,
-    ReceiveNftMsg,
+    BuyerSummaryResponse, ClearingResponse, CommitmentResponse, CurrentPriceResponse,
+    CurrentValsetResponse, EpochStatusResponse, ExecuteMsg, FeeAccountingResponse, FeeRecipient,
+    InstantiateMsg, MarketStateResponse, MigrateMsg, ParameterHistoryResponse, ProviderShare,
+    ProviderSharesResponse, PurchaseResponse, PurchasesResponse, QueryMsg, QuoteResponse,
+    ReceiveMsg, ReceiveNftMsg, ReconciliationResponse, RolesResponse, SalePhaseResponse,
+    UnitsProvidedResponse, ValidatorShareResponse, ValsetUpdate, VestingStatusResponse,
+};
+use crate::pool::EarningsIndex;
+use crate::roles::{
+    ensure_role, ensure_seller, ensure_seller_or_role, grant_role, revoke_role, role_members, Role,
 };
 use crate::state::{
-    CheckedNft, MarketConfig, MARKET_CONFIG, MARKET_STATE, TOTAL_WITHDRAWN, UNIX_START,
+    purchases, CheckedNft, MarketConfig, Phase, Purchase, Settlement, BUYER_TOTALS, CLEARING,
+    COMMITMENTS, COMMITTED_UNITS_CLAIMED, EARNINGS_CHECKPOINTS, EARNINGS_INDEX, EARNINGS_WITHDRAWN,
+    EPOCH_UNITS_SOLD, FEES_ACCRUED, FEES_CLAIMED, FINALIZED, FINALIZED_SHARES, MARKET_CONFIG,
+    MARKET_STATE, NEXT_PURCHASE_ID, OWNER, PARAMETER_CHANGES, PAUSED, PENDING_OWNER,
+    PHASE_UNITS_BOUGHT, POOL_SHARES, TOTAL_COMMITTED, TOTAL_FEES_ACCRUED, TOTAL_FEES_CLAIMED,
+    TOTAL_POOL_SHARES, TOTAL_UNITS_PROVIDED, TOTAL_UNSOLD_RECLAIMED, TOTAL_VESTING_LOCKED,
+    TOTAL_WITHDRAWN, UNITS_PROVIDED, UNIX_START, UNSOLD_RECLAIMED, VALIDATOR_SHARES_ACCRUED,
+    VALIDATOR_SHARES_CLAIMED, VALSET, VESTING,
 };
+use crate::vesting::Vesting;
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:dutch-auction-vault";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// the furthest ahead of the current block an auction can be scheduled, in seconds
+pub const MAX_START_DELAY: u64 = 60 * 60 * 24 * 90;
+/// the smallest supply an auction can be scheduled with, unless the
+/// instantiate message sets its own
+pub const MIN_TOTAL_UNITS: Uint128 = Uint128::new(1_000);
+
+// pagination defaults
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 /// Handling contract instantiation
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -52,15 +85,48 @@ pub fn instantiate(
         start_price: msg.start_price,
         min_price: msg.min_price,
         target_duration: msg.target_duration,
+        price_curve: msg.price_curve.unwrap_or_default(),
+        end_time: msg.end_time,
+        settlement: msg.settlement.unwrap_or_default(),
+        vesting: msg.vesting,
+        epoch_release: msg.epoch_release,
+        max_units_per_buyer: msg.max_units_per_buyer,
+        max_units_per_tx: msg.max_units_per_tx,
+        min_units_per_tx: msg.min_units_per_tx,
+        min_total_units: msg.min_total_units.unwrap_or(MIN_TOTAL_UNITS),
+        sale_phases: msg.sale_phases,
+        dao: msg
+            .dao
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?,
+        fees: msg
+            .fees
+            .into_iter()
+            .map(|fee| {
+                Ok(Fee::new(
+                    fee.bps,
+                    deps.api.addr_validate(&fee.address)?,
+                    fee.label,
+                ))
+            })
+            .collect::<StdResult<Vec<Fee>>>()?,
+        referral_bps: msg.referral_bps.unwrap_or_default(),
+        validator_share_bps: msg.validator_share_bps.unwrap_or_default(),
     };
 
     market_config.validate()?;
 
     MARKET_CONFIG.save(deps.storage, &market_config)?;
+    let owner = msg
+        .owner
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .unwrap_or(info.sender);
+    OWNER.save(deps.storage, &owner)?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", owner))
 }
 
 /// Handling contract migration
@@ -69,13 +135,21 @@ pub fn instantiate(
 /// - only contract admin can migrate, so admin has to be set at contract initiation time
 /// Handling contract execution
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
-    match msg {
-        // Find matched incoming message variant and execute them with your custom logic.
-        //
-        // With `Response` type, it is possible to dispatch message to invoke external logic.
-        // See: https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#dispatching-messages
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored_version = check_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if stored_version < v0_2() {
+        let owner = msg
+            .owner
+            .map(|addr| deps.api.addr_validate(&addr))
+            .transpose()?;
+        migrate_from_v0_1(deps.storage, owner)?;
     }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
 }
 
 /// Handling contract execution
@@ -153,6 +227,25 @@ pub fn execute(
             handle_schedule_auction(deps, env, info.sender, start_time_unix)
         }
         ExecuteMsg::WithdrawEarnings {} => withdraw_earnings(deps, env, info),
+        ExecuteMsg::Finalize {} => finalize(deps, env, info),
+        ExecuteMsg::CancelAuction {} => cancel_auction(deps, env, info),
+        ExecuteMsg::Reconcile {} => reconcile(deps, env, info),
+        ExecuteMsg::ClaimFees {} => claim_fees(deps, info),
+        ExecuteMsg::Settle {} => settle(deps, env),
+        ExecuteMsg::ClaimCommitment {} => claim_commitment(deps, env, info),
+        ExecuteMsg::ClaimVested {} => claim_vested(deps, env, info),
+        ExecuteMsg::GrantRole { role, address } => execute_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
+        ExecuteMsg::TransferOwnership { new_owner } => transfer_ownership(deps, info, new_owner),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::Pause {} => set_paused(deps, env, info, true),
+        ExecuteMsg::Unpause {} => set_paused(deps, env, info, false),
+        ExecuteMsg::UpdateValset(update) => update_valset(deps, info, update),
+        ExecuteMsg::ClaimValidatorShare {} => claim_validator_share(deps, info),
+        ExecuteMsg::UpdateParameters {
+            min_price,
+            target_duration,
+        } => update_parameters(deps, env, info, min_price, target_duration),
     }
 }
 
@@ -162,18 +255,39 @@ pub fn handle_schedule_auction(
     sender: Addr,
     unix_start_draft: Uint64,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
     let market_config = MARKET_CONFIG.load(deps.storage)?;
-
-    if let Some(seller_addr) = market_config.seller_address {
-        if seller_addr != sender {
-            return Err(ContractError::Unauthorized {});
+    let by_dao = is_dao(&market_config, &sender);
+    if !by_dao {
+        ensure_seller(&market_config, &sender)?;
+    }
+    if UNIX_START.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::AuctionAlreadyScheduled {});
+    }
+    let now = env.block.time.seconds();
+    if unix_start_draft.u64() < now {
+        return Err(ContractError::AuctionStartTimeInThePast {});
+    }
+    if unix_start_draft.u64() > now + MAX_START_DELAY {
+        return Err(ContractError::AuctionStartTimeTooFarInFuture {});
+    }
+    if let Some(end_time) = market_config.end_time {
+        if end_time <= unix_start_draft {
+            return Err(ContractError::AuctionEndTimeBeforeStartTime {});
         }
-    } else {
-        return Err(ContractError::Unauthorized {});
     }
-    let total_units = market_config
-        .unit_asset
-        .query_balance(&deps.querier, &env.contract.address)?;
+
+    // only units provided through ProvideUnits are put up for sale, anything
+    // sent to the vault directly is left for Reconcile to credit
+    let total_units = TOTAL_UNITS_PROVIDED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    if total_units < market_config.min_total_units {
+        return Err(ContractError::InsufficientSupply {
+            total_units,
+            min_total_units: market_config.min_total_units,
+        });
+    }
 
     let market = Market::new(
         market_config.start_price,
@@ -182,22 +296,25 @@ pub fn handle_schedule_auction(
         market_config.target_duration,
         Uint128::zero(),
         total_units,
+        market_config.price_curve,
     );
 
     MARKET_STATE.save(deps.storage, &market)?;
-
-    let unix_start = UNIX_START.may_load(deps.storage)?;
-
-    if unix_start.is_some() {
-        return Err(ContractError::AuctionAlreadyScheduled {});
-    }
-    if unix_start_draft.u64() < env.block.time.seconds() {
-        return Err(ContractError::AuctionStartTimeInThePast {});
-    }
-
     UNIX_START.save(deps.storage, &unix_start_draft)?;
+    let events = if by_dao {
+        vec![record_change(
+            deps.storage,
+            &env,
+            GovernanceChange::ScheduleAuction {
+                start_time_unix: unix_start_draft,
+            },
+        )?]
+    } else {
+        vec![]
+    };
 
     Ok(Response::new()
+        .add_events(events)
         .add_attribute("method", "schedule_auction")
         .add_attribute("start_time_unix", unix_start_draft))
 }
@@ -211,33 +328,189 @@ pub fn handle_recv_action(
     recv_action: ReceiveMsg,
 ) -> Result<Response, ContractError> {
     match recv_action {
-        ReceiveMsg::BuyUnits { units } => {
-            buy_units(deps, env, sender, received_asset, received_amount, units)
+        ReceiveMsg::BuyUnits {
+            units,
+            max_price,
+            max_cost,
+            deadline,
+            referrer,
+            proof,
+        } => {
+            assert_deadline(&env, deadline)?;
+            let terms = PurchaseTerms {
+                max_price,
+                max_cost,
+                referrer: referrer
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?,
+                proof,
+            };
+            buy_units(
+                deps,
+                env,
+                sender,
+                received_asset,
+                received_amount,
+                units,
+                terms,
+            )
+        }
+        ReceiveMsg::BuyWithAmount {
+            min_units,
+            deadline,
+            referrer,
+            proof,
+        } => {
+            assert_deadline(&env, deadline)?;
+            let terms = PurchaseTerms {
+                referrer: referrer
+                    .map(|addr| deps.api.addr_validate(&addr))
+                    .transpose()?,
+                proof,
+                ..PurchaseTerms::default()
+            };
+            buy_with_amount(
+                deps,
+                env,
+                sender,
+                received_asset,
+                received_amount,
+                min_units,
+                terms,
+            )
         }
         ReceiveMsg::ProvideUnits {} => {
             provide_units(deps, env, sender, received_asset, received_amount)
         }
+        ReceiveMsg::Commit {} => commit(deps, env, sender, received_asset, received_amount),
     }
 }
 
 pub fn provide_units(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: Addr,
     received_asset: CheckedFungibleAsset,
     received_amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // just assert that checked asset is the correct one
     let market_config = MARKET_CONFIG.load(deps.storage)?;
     if market_config.unit_asset != received_asset {
         return Err(ContractError::WrongAsset {});
     }
+
+    let phase = auction_phase(deps.as_ref(), &env)?;
+    if matches!(phase, Phase::Closed {}) {
+        return Err(ContractError::AuctionClosed {});
+    }
+    record_units_provided(deps.storage, &sender, received_amount)?;
+    // top up the supply of a live market
+    if matches!(phase, Phase::Scheduled {} | Phase::Open {}) {
+        MARKET_STATE.update(deps.storage, |mut market| -> StdResult<_> {
+            market.total_units += received_amount;
+            Ok(market)
+        })?;
+    }
+
     Ok(Response::new()
         .add_attribute("method", "provide_units")
         .add_attribute("provider", sender)
         .add_attribute("units", received_amount.to_string()))
 }
 
+/// credits a provider with units added to the pool, before they are added to
+/// the market. the provider is issued pool shares at the pool's current units
+/// per share, checkpointed past the proceeds already earned
+fn record_units_provided(
+    storage: &mut dyn Storage,
+    provider: &Addr,
+    units: Uint128,
+) -> StdResult<()> {
+    let index = accrue_earnings(storage)?;
+    let pooled_units = match MARKET_STATE.may_load(storage)? {
+        Some(market) => market.units_available(),
+        None => TOTAL_UNITS_PROVIDED.may_load(storage)?.unwrap_or_default(),
+    };
+    let total_shares = TOTAL_POOL_SHARES.may_load(storage)?.unwrap_or_default();
+    let shares = if total_shares.is_zero() || pooled_units.is_zero() {
+        units
+    } else {
+        units.multiply_ratio(total_shares, pooled_units)
+    };
+    let checkpoint = index.earnings_of(shares, true)?;
+
+    UNITS_PROVIDED.update(storage, provider, |provided| -> StdResult<_> {
+        Ok(provided.unwrap_or_default() + units)
+    })?;
+    let total_provided = TOTAL_UNITS_PROVIDED.may_load(storage)?.unwrap_or_default();
+    TOTAL_UNITS_PROVIDED.save(storage, &(total_provided + units))?;
+    POOL_SHARES.update(storage, provider, |held| -> StdResult<_> {
+        Ok(held.unwrap_or_default() + shares)
+    })?;
+    TOTAL_POOL_SHARES.save(storage, &(total_shares + shares))?;
+    EARNINGS_CHECKPOINTS.update(storage, provider, |earned| -> StdResult<_> {
+        Ok(earned.unwrap_or_default() + checkpoint)
+    })?;
+    Ok(())
+}
+
+/// limits a buyer places on the price of a purchase, who referred them, and
+/// their proof of being allowlisted
+#[derive(Default)]
+struct PurchaseTerms {
+    max_price: Option<Decimal>,
+    max_cost: Option<Uint128>,
+    referrer: Option<Addr>,
+    proof: Option<AllowlistProof>,
+}
+
+fn assert_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PAUSED.may_load(storage)?.unwrap_or(false) {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+fn assert_deadline(env: &Env, deadline: Option<Uint64>) -> Result<(), ContractError> {
+    match deadline {
+        Some(deadline) if env.block.time.seconds() > deadline.u64() => {
+            Err(ContractError::DeadlineExceeded {})
+        }
+        _ => Ok(()),
+    }
+}
+
+fn buy_with_amount(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    received_asset: CheckedFungibleAsset,
+    received_amount: Uint128,
+    min_units: Uint128,
+    terms: PurchaseTerms,
+) -> Result<Response, ContractError> {
+    let unix_start = UNIX_START.load(deps.storage)?;
+    let time_elapsed = time_elapsed(&env, unix_start)?;
+    let market_state = MARKET_STATE.load(deps.storage)?;
+
+    let units = market_state.calculate_received(received_amount, time_elapsed)?;
+    if units.is_zero() {
+        return Err(ContractError::ZeroTokens {});
+    }
+    if units < min_units {
+        return Err(ContractError::MinUnitsNotMet { units, min_units });
+    }
+
+    buy_units(
+        deps,
+        env,
+        sender,
+        received_asset,
+        received_amount,
+        units,
+        terms,
+    )
+}
+
 fn buy_units(
     deps: DepsMut,
     env: Env,
@@ -245,20 +518,66 @@ fn buy_units(
     received_asset: CheckedFungibleAsset,
     received_amount: Uint128,
     units: Uint128,
+    terms: PurchaseTerms,
 ) -> Result<Response, ContractError> {
+    if units.is_zero() {
+        return Err(ContractError::ZeroTokens {});
+    }
+    assert_not_paused(deps.storage)?;
     let market_config = MARKET_CONFIG.load(deps.storage)?;
     if market_config.spend_asset != received_asset {
         return Err(ContractError::WrongAsset {});
     }
+    if market_config.settlement != (Settlement::Continuous {}) {
+        return Err(ContractError::WrongSettlement {});
+    }
+    if terms.referrer.as_ref() == Some(&sender) {
+        return Err(ContractError::SelfReferral {});
+    }
     let unix_start = UNIX_START.load(deps.storage)?;
-    let time_elapsed: Uint128 = Uint64::from(env.block.time.seconds())
-        .checked_sub(unix_start)
-        .map_err(|_e| ContractError::AuctionHasNotStartedYet {})?
-        .into();
+    let time_elapsed = time_elapsed(&env, unix_start)?;
+    if matches!(auction_phase(deps.as_ref(), &env)?, Phase::Closed {}) {
+        return Err(ContractError::AuctionClosed {});
+    }
+    check_purchase_limits(deps.storage, &market_config, &sender, units)?;
+    apply_sale_phase(
+        deps.storage,
+        &env,
+        &market_config,
+        &sender,
+        units,
+        terms.proof.as_ref(),
+    )?;
 
     let mut market_state = MARKET_STATE.load(deps.storage)?;
+    if let Some(release) = &market_config.epoch_release {
+        let status = epoch_status(deps.as_ref(), &env, release, &market_state, unix_start)?;
+        if units > status.remaining_allocation {
+            return Err(ContractError::EpochAllocationExceeded {
+                remaining: status.remaining_allocation,
+            });
+        }
+        EPOCH_UNITS_SOLD.update(
+            deps.storage,
+            status.current_epoch.u64(),
+            |sold| -> StdResult<_> { Ok(sold.unwrap_or_default() + units) },
+        )?;
+    }
+    if let Some(max_price) = terms.max_price {
+        let price = market_state.calculate_price(units, time_elapsed)?;
+        if price > max_price {
+            return Err(ContractError::MaxPriceExceeded { price, max_price });
+        }
+    }
     let Cost(cost) = market_state.buy_units(units, time_elapsed)?;
+    if let Some(max_cost) = terms.max_cost {
+        if cost > max_cost {
+            return Err(ContractError::MaxCostExceeded { cost, max_cost });
+        }
+    }
     MARKET_STATE.save(deps.storage, &market_state)?;
+    let purchase_id = record_purchase(deps.storage, &env, &sender, units, cost)?;
+    let fee_events = accrue_fees(deps.storage, &market_config, terms.referrer.as_ref(), cost)?;
 
     let remainder = received_amount
         .checked_sub(cost)
@@ -270,15 +589,513 @@ fn buy_units(
         vec![received_asset.into_send_message(remainder, &sender)?]
     };
 
-    let payout_msg = market_config.unit_asset.into_send_message(units, &sender)?;
-
+    let payout_msgs = deliver_units(deps.storage, &market_config, &sender, units)?;
     Ok(Response::new()
-        .add_message(payout_msg)
+        .add_messages(payout_msgs)
         .add_messages(change_msgs)
+        .add_events(fee_events)
         .add_attribute("method", "buy_units")
         .add_attribute("buyer", sender)
         .add_attribute("units", units.to_string())
-        .add_attribute("cost", cost.to_string()))
+        .add_attribute("cost", cost.to_string())
+        .add_attribute("purchase_id", purchase_id.to_string()))
+}
+
+/// enforces the per purchase bounds and the buyer's cap across every purchase
+/// they have made
+fn check_purchase_limits(
+    storage: &dyn Storage,
+    market_config: &MarketConfig,
+    buyer: &Addr,
+    units: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(min_units_per_tx) = market_config.min_units_per_tx {
+        if units < min_units_per_tx {
+            return Err(ContractError::BelowMinUnitsPerTx {
+                units,
+                min_units_per_tx,
+            });
+        }
+    }
+    if let Some(max_units_per_tx) = market_config.max_units_per_tx {
+        if units > max_units_per_tx {
+            return Err(ContractError::MaxUnitsPerTxExceeded {
+                units,
+                max_units_per_tx,
+            });
+        }
+    }
+    if let Some(max_units_per_buyer) = market_config.max_units_per_buyer {
+        let bought = BUYER_TOTALS
+            .may_load(storage, buyer)?
+            .unwrap_or_default()
+            .units;
+        let remaining = max_units_per_buyer.saturating_sub(bought);
+        if units > remaining {
+            return Err(ContractError::MaxUnitsPerBuyerExceeded { remaining });
+        }
+    }
+    Ok(())
+}
+
+/// enforces the sale phase open at the block time, drawing purchases in a
+/// gated phase from the buyer's allocation
+fn apply_sale_phase(
+    storage: &mut dyn Storage,
+    env: &Env,
+    market_config: &MarketConfig,
+    buyer: &Addr,
+    units: Uint128,
+    proof: Option<&AllowlistProof>,
+) -> Result<(), ContractError> {
+    if market_config.sale_phases.is_empty() {
+        return Ok(());
+    }
+    let (phase_id, phase) = active_phase(
+        &market_config.sale_phases,
+        Uint64::from(env.block.time.seconds()),
+    )
+    .ok_or(ContractError::NoActiveSalePhase {})?;
+    let merkle_root = match &phase.merkle_root {
+        Some(merkle_root) => merkle_root,
+        None => return Ok(()),
+    };
+
+    let remaining = remaining_allocation(storage, phase_id, merkle_root, buyer, proof)?;
+    if units > remaining {
+        return Err(ContractError::AllowlistAllocationExceeded { remaining });
+    }
+    PHASE_UNITS_BOUGHT.update(storage, (phase_id, buyer), |bought| -> StdResult<_> {
+        Ok(bought.unwrap_or_default() + units)
+    })?;
+    Ok(())
+}
+
+/// the units left in a buyer's allocation for a gated phase, once their
+/// proof checks out against the phase's merkle root
+fn remaining_allocation(
+    storage: &dyn Storage,
+    phase_id: u64,
+    merkle_root: &str,
+    buyer: &Addr,
+    proof: Option<&AllowlistProof>,
+) -> Result<Uint128, ContractError> {
+    let proof = proof.ok_or(ContractError::NotAllowlisted {})?;
+    if !proof.verify(merkle_root, buyer)? {
+        return Err(ContractError::NotAllowlisted {});
+    }
+    let bought = PHASE_UNITS_BOUGHT
+        .may_load(storage, (phase_id, buyer))?
+        .unwrap_or_default();
+    Ok(proof.allocation.saturating_sub(bought))
+}
+
+/// sends bought units to the buyer, or locks them under the vesting schedule
+fn deliver_units(
+    storage: &mut dyn Storage,
+    market_config: &MarketConfig,
+    buyer: &Addr,
+    units: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    if market_config.vesting.is_none() {
+        return Ok(vec![market_config
+            .unit_asset
+            .clone()
+            .into_send_message(units, buyer)?]);
+    }
+
+    VESTING.update(storage, buyer, |vesting| -> StdResult<_> {
+        let mut vesting = vesting.unwrap_or_default();
+        vesting.total += units;
+        Ok(vesting)
+    })?;
+    let total_locked = TOTAL_VESTING_LOCKED.may_load(storage)?.unwrap_or_default();
+    TOTAL_VESTING_LOCKED.save(storage, &(total_locked + units))?;
+    Ok(vec![])
+}
+
+fn claim_vested(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let mut vesting = VESTING
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoVesting {})?;
+    let vested = vested_units(deps.as_ref(), &env, &market_config, &vesting)?;
+
+    let to_claim = vested - vesting.claimed;
+    if to_claim.is_zero() {
+        return Err(ContractError::NothingVested {});
+    }
+
+    vesting.claimed += to_claim;
+    VESTING.save(deps.storage, &info.sender, &vesting)?;
+    let total_locked = TOTAL_VESTING_LOCKED.load(deps.storage)?;
+    TOTAL_VESTING_LOCKED.save(deps.storage, &(total_locked - to_claim))?;
+
+    let claim_msg = market_config
+        .unit_asset
+        .into_send_message(to_claim, &info.sender)?;
+
+    Ok(Response::new()
+        .add_message(claim_msg)
+        .add_attribute("method", "claim_vested")
+        .add_attribute("buyer", info.sender)
+        .add_attribute("units", to_claim.to_string()))
+}
+
+/// the units of a vesting record released at the current block time
+fn vested_units(
+    deps: Deps,
+    env: &Env,
+    market_config: &MarketConfig,
+    vesting: &Vesting,
+) -> StdResult<Uint128> {
+    let (Some(schedule), Some(unix_start)) =
+        (&market_config.vesting, UNIX_START.may_load(deps.storage)?)
+    else {
+        return Ok(Uint128::zero());
+    };
+    Ok(schedule.vested(
+        unix_start,
+        vesting.total,
+        Uint64::from(env.block.time.seconds()),
+    ))
+}
+
+/// stores the purchase in the ledger and adds it to the buyer's totals,
+/// returning the purchase id
+fn record_purchase(
+    storage: &mut dyn Storage,
+    env: &Env,
+    buyer: &Addr,
+    units: Uint128,
+    cost: Uint128,
+) -> StdResult<u64> {
+    let id = NEXT_PURCHASE_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_PURCHASE_ID.save(storage, &(id + 1))?;
+    purchases().save(
+        storage,
+        id,
+        &Purchase {
+            buyer: buyer.clone(),
+            units,
+            cost,
+            price: Decimal::from_ratio(cost, units),
+            time: Uint64::from(env.block.time.seconds()),
+        },
+    )?;
+    BUYER_TOTALS.update(storage, buyer, |totals| -> StdResult<_> {
+        let mut totals = totals.unwrap_or_default();
+        totals.purchase_count += 1;
+        totals.units += units;
+        totals.cost += cost;
+        Ok(totals)
+    })?;
+    Ok(id)
+}
+
+/// accrues each fee's cut of a sale, plus the referral cut when the buyer
+/// names a referrer, returning a fee_distribution event if any fee applies
+fn accrue_fees(
+    storage: &mut dyn Storage,
+    market_config: &MarketConfig,
+    referrer: Option<&Addr>,
+    cost: Uint128,
+) -> StdResult<Vec<Event>> {
+    let mut cuts: Vec<(String, &Addr, Uint128)> = market_config
+        .fees
+        .iter()
+        .map(|fee| (fee.label.clone(), &fee.address, fee.amount_of(cost)))
+        .collect();
+    if let Some(referrer) = referrer {
+        if !market_config.referral_bps.is_zero() {
+            cuts.push((
+                "referral".to_string(),
+                referrer,
+                bps_of(market_config.referral_bps, cost),
+            ));
+        }
+    }
+
+    let mut total_fees = Uint128::zero();
+    let mut attrs = vec![];
+    for (label, recipient, amount) in cuts {
+        FEES_ACCRUED.update(storage, recipient, |accrued| -> StdResult<_> {
+            Ok(accrued.unwrap_or_default() + amount)
+        })?;
+        total_fees += amount;
+        attrs.push((label, amount.to_string()));
+    }
+    let total_accrued = TOTAL_FEES_ACCRUED.may_load(storage)?.unwrap_or_default();
+    TOTAL_FEES_ACCRUED.save(storage, &(total_accrued + total_fees))?;
+
+    if attrs.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(vec![Event::new("fee_distribution")
+        .add_attribute("spend_amount", cost.to_string())
+        .add_attributes(attrs)])
+}
+
+fn commit(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    received_asset: CheckedFungibleAsset,
+    received_amount: Uint128,
+) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage)?;
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    if market_config.settlement != (Settlement::UniformClearing {}) {
+        return Err(ContractError::WrongSettlement {});
+    }
+    if market_config.spend_asset != received_asset {
+        return Err(ContractError::WrongAsset {});
+    }
+    let unix_start = UNIX_START.load(deps.storage)?;
+    let time_elapsed = time_elapsed(&env, unix_start)?;
+    if matches!(auction_phase(deps.as_ref(), &env)?, Phase::Closed {}) {
+        return Err(ContractError::AuctionClosed {});
+    }
+
+    COMMITMENTS.update(deps.storage, &sender, |committed| -> StdResult<_> {
+        Ok(committed.unwrap_or_default() + received_amount)
+    })?;
+    let total_committed =
+        TOTAL_COMMITTED.may_load(deps.storage)?.unwrap_or_default() + received_amount;
+    TOTAL_COMMITTED.save(deps.storage, &total_committed)?;
+
+    // once the commitments cover the whole supply at the current price the
+    // clock stops and the auction clears
+    let market_state = MARKET_STATE.load(deps.storage)?;
+    let events = if market_state.clears_supply(total_committed, time_elapsed)? {
+        settle_commitments(
+            deps.storage,
+            &market_config,
+            market_state,
+            total_committed,
+            time_elapsed,
+        )?
+    } else {
+        vec![]
+    };
+
+    Ok(Response::new()
+        .add_events(events)
+        .add_attribute("method", "commit")
+        .add_attribute("buyer", sender)
+        .add_attribute("amount", received_amount.to_string())
+        .add_attribute("total_committed", total_committed.to_string()))
+}
+
+/// sells the committed units at a single price and accrues the fees on the sale
+fn settle_commitments(
+    storage: &mut dyn Storage,
+    market_config: &MarketConfig,
+    mut market_state: Market,
+    committed: Uint128,
+    time_elapsed: Uint128,
+) -> Result<Vec<Event>, ContractError> {
+    let clearing = market_state.clear_commitments(committed, time_elapsed)?;
+    MARKET_STATE.save(storage, &market_state)?;
+    CLEARING.save(storage, &clearing)?;
+
+    let mut events = vec![Event::new("clearing")
+        .add_attribute("price", clearing.price.to_string())
+        .add_attribute("units", clearing.units.to_string())
+        .add_attribute("cost", clearing.cost.to_string())
+        .add_attribute("committed", clearing.committed.to_string())];
+    events.extend(accrue_fees(storage, market_config, None, clearing.cost)?);
+    Ok(events)
+}
+
+/// settles the commitments at the price the clock reached when the auction
+/// closed, unless the auction already cleared
+fn ensure_settled(deps: &mut DepsMut, env: &Env) -> Result<Vec<Event>, ContractError> {
+    if CLEARING.exists(deps.storage) {
+        return Ok(vec![]);
+    }
+    if !matches!(auction_phase(deps.as_ref(), env)?, Phase::Closed {}) {
+        return Err(ContractError::AuctionNotClosed {});
+    }
+
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let unix_start = UNIX_START.load(deps.storage)?;
+    let closed_after = match market_config.end_time {
+        Some(end_time) => Uint128::from(end_time.saturating_sub(unix_start)),
+        None => market_config.target_duration,
+    };
+    let time_elapsed = time_elapsed(env, unix_start)?.min(closed_after);
+    let committed = TOTAL_COMMITTED.may_load(deps.storage)?.unwrap_or_default();
+    let market_state = MARKET_STATE.load(deps.storage)?;
+
+    settle_commitments(
+        deps.storage,
+        &market_config,
+        market_state,
+        committed,
+        time_elapsed,
+    )
+}
+
+fn settle(mut deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    if market_config.settlement != (Settlement::UniformClearing {}) {
+        return Err(ContractError::WrongSettlement {});
+    }
+    if CLEARING.exists(deps.storage) {
+        return Err(ContractError::AlreadySettled {});
+    }
+
+    let events = ensure_settled(&mut deps, &env)?;
+
+    Ok(Response::new()
+        .add_events(events)
+        .add_attribute("method", "settle"))
+}
+
+fn claim_commitment(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    if market_config.settlement != (Settlement::UniformClearing {}) {
+        return Err(ContractError::WrongSettlement {});
+    }
+    let committed = COMMITMENTS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoCommitment {})?;
+
+    let events = ensure_settled(&mut deps, &env)?;
+    let clearing = CLEARING.load(deps.storage)?;
+
+    // every buyer gets their share of the cleared units, and is refunded their
+    // share of whatever the clearing did not spend
+    let units = committed.multiply_ratio(clearing.units, clearing.committed);
+    let refund = committed.multiply_ratio(clearing.committed - clearing.cost, clearing.committed);
+    let cost = committed - refund;
+
+    COMMITMENTS.remove(deps.storage, &info.sender);
+    let units_claimed = COMMITTED_UNITS_CLAIMED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    COMMITTED_UNITS_CLAIMED.save(deps.storage, &(units_claimed + units))?;
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !units.is_zero() {
+        record_purchase(deps.storage, &env, &info.sender, units, cost)?;
+        msgs.extend(deliver_units(
+            deps.storage,
+            &market_config,
+            &info.sender,
+            units,
+        )?);
+    }
+    if !refund.is_zero() {
+        msgs.push(
+            market_config
+                .spend_asset
+                .into_send_message(refund, &info.sender)?,
+        );
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_events(events)
+        .add_attribute("method", "claim_commitment")
+        .add_attribute("buyer", info.sender)
+        .add_attribute("units", units.to_string())
+        .add_attribute("cost", cost.to_string())
+        .add_attribute("refund", refund.to_string()))
+}
+
+/// where the release schedule stands at the current block time
+fn epoch_status(
+    deps: Deps,
+    env: &Env,
+    release: &EpochRelease,
+    market: &Market,
+    unix_start: Uint64,
+) -> StdResult<EpochStatusResponse> {
+    let now = Uint64::from(env.block.time.seconds());
+    let epoch = release.epoch(unix_start);
+    let current_epoch = epoch.current_epoch(now);
+    let epoch_units_sold = EPOCH_UNITS_SOLD
+        .may_load(deps.storage, current_epoch.u64())?
+        .unwrap_or_default();
+
+    Ok(EpochStatusResponse {
+        current_epoch,
+        epoch_allocation: release.allocation(market.total_units, current_epoch),
+        remaining_allocation: release.remaining(
+            market.total_units,
+            current_epoch,
+            market.units_sold,
+            epoch_units_sold,
+        ),
+        next_epoch_start: epoch.next_epoch_start(now),
+    })
+}
+
+/// the current phase of the auction, derived from the schedule, the end
+/// condition and the units left for sale
+fn auction_phase(deps: Deps, env: &Env) -> StdResult<Phase> {
+    let (Some(market), Some(unix_start)) = (
+        MARKET_STATE.may_load(deps.storage)?,
+        UNIX_START.may_load(deps.storage)?,
+    ) else {
+        return Ok(Phase::NotScheduled {});
+    };
+    let Ok(time_elapsed) = time_elapsed(env, unix_start) else {
+        return Ok(Phase::Scheduled {});
+    };
+
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let out_of_time = match market_config.end_time {
+        Some(end_time) => env.block.time.seconds() >= end_time.u64(),
+        None => time_elapsed >= market.target_duration,
+    };
+
+    if out_of_time || market.is_sold_out() {
+        Ok(Phase::Closed {})
+    } else {
+        Ok(Phase::Open {})
+    }
+}
+
+/// seconds elapsed since `unix_start`, erroring if the auction has not started yet
+fn time_elapsed(env: &Env, unix_start: Uint64) -> Result<Uint128, ContractError> {
+    let time_elapsed = Uint64::from(env.block.time.seconds())
+        .checked_sub(unix_start)
+        .map_err(|_e| ContractError::AuctionHasNotStartedYet {})?;
+    Ok(time_elapsed.into())
+}
+
+/// spreads the proceeds made since the last accrual over the pool shares,
+/// fees coming out of the proceeds before the providers are paid
+fn accrue_earnings(storage: &mut dyn Storage) -> StdResult<EarningsIndex> {
+    let mut index = EARNINGS_INDEX.may_load(storage)?.unwrap_or_default();
+    if let Some(market) = MARKET_STATE.may_load(storage)? {
+        let total_fees = TOTAL_FEES_ACCRUED.may_load(storage)?.unwrap_or_default();
+        let proceeds = market.total_spent.saturating_sub(total_fees);
+        let total_shares = TOTAL_POOL_SHARES.may_load(storage)?.unwrap_or_default();
+        index.accrue(proceeds, total_shares);
+        EARNINGS_INDEX.save(storage, &index)?;
+    }
+    Ok(index)
+}
+
+/// the proceeds a provider's shares have earned since they were issued
+fn provider_earnings(
+    storage: &dyn Storage,
+    index: &EarningsIndex,
+    provider: &Addr,
+) -> StdResult<Uint128> {
+    let shares = POOL_SHARES.may_load(storage, provider)?.unwrap_or_default();
+    let checkpoint = EARNINGS_CHECKPOINTS
+        .may_load(storage, provider)?
+        .unwrap_or_default();
+    Ok(index.earnings_of(shares, false)?.saturating_sub(checkpoint))
 }
 
 fn withdraw_earnings(
@@ -287,53 +1104,924 @@ fn withdraw_earnings(
     info: MessageInfo,
 ) -> Result<Response, ContractError> {
     let market_config = MARKET_CONFIG.load(deps.storage)?;
-    if market_config.seller_address != Some(info.sender.clone()) {
+    if !UNITS_PROVIDED.has(deps.storage, &info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    let market_state = MARKET_STATE.load(deps.storage)?;
+    if !MARKET_STATE.exists(deps.storage) {
+        return Err(ContractError::AuctionNotScheduled {});
+    }
+    let index = accrue_earnings(deps.storage)?;
+    let earned = provider_earnings(deps.storage, &index, &info.sender)?;
+    let withdrawn = EARNINGS_WITHDRAWN
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    // issuing shares rounds in the pool's favour, which can leave what is
+    // earned a hair below what was already withdrawn
+    let to_withdraw = earned.saturating_sub(withdrawn);
+
+    EARNINGS_WITHDRAWN.save(deps.storage, &info.sender, &(withdrawn + to_withdraw))?;
     let total_withdrawn = TOTAL_WITHDRAWN
         .may_load(deps.storage)?
         .unwrap_or(Uint128::zero());
+    TOTAL_WITHDRAWN.save(deps.storage, &(total_withdrawn + to_withdraw))?;
 
-    let to_withdraw = market_state
-        .total_spent
-        .checked_sub(total_withdrawn)
-        .map_err(|_e| ContractError::InsufficientFunds {})?;
+    // when the market opts in, a cut of the earnings goes to the relayed
+    // validators by power, the provider keeping the rest
+    let distributable = bps_of(market_config.validator_share_bps, to_withdraw);
+    let (validator_shares, undistributed) = match VALSET.may_load(deps.storage)? {
+        Some(valset) if !distributable.is_zero() => valset.split(distributable),
+        _ => (vec![], distributable),
+    };
+    let payout = to_withdraw - distributable + undistributed;
+    for (validator, share) in &validator_shares {
+        VALIDATOR_SHARES_ACCRUED.update(deps.storage, validator, |accrued| -> StdResult<_> {
+            Ok(accrued.unwrap_or_default() + share)
+        })?;
+    }
 
-    TOTAL_WITHDRAWN.save(deps.storage, &(total_withdrawn + to_withdraw))?;
+    let payout_msgs = if payout.is_zero() {
+        vec![]
+    } else {
+        vec![market_config
+            .spend_asset
+            .into_send_message(payout, &info.sender)?]
+    };
+
+    Ok(Response::new()
+        .add_messages(payout_msgs)
+        .add_attribute("method", "withdraw_earnings")
+        .add_attribute("provider", info.sender)
+        .add_attribute("payout", payout.to_string())
+        .add_attribute("distributed", (to_withdraw - payout).to_string()))
+}
+
+fn claim_fees(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let accrued = FEES_ACCRUED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let claimed = FEES_CLAIMED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
 
-    let payout_msg = market_config
+    let to_claim = accrued.saturating_sub(claimed);
+    if to_claim.is_zero() {
+        return Err(ContractError::NoFeesToClaim {});
+    }
+
+    FEES_CLAIMED.save(deps.storage, &info.sender, &accrued)?;
+    let total_claimed = TOTAL_FEES_CLAIMED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    TOTAL_FEES_CLAIMED.save(deps.storage, &(total_claimed + to_claim))?;
+
+    let claim_msg = market_config
         .spend_asset
-        .into_send_message(to_withdraw, &info.sender)?;
+        .into_send_message(to_claim, &info.sender)?;
 
     Ok(Response::new()
-        .add_message(payout_msg)
-        .add_attribute("method", "withdraw_earnings")
-        .add_attribute("seller", info.sender)
-        .add_attribute("payout", to_withdraw.to_string()))
+        .add_message(claim_msg)
+        .add_attribute("method", "claim_fees")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("amount", to_claim.to_string()))
+}
+
+fn finalize(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    // the DAO finalizes on the seller's behalf
+    let by_dao = is_dao(&market_config, &info.sender);
+    let provider = match &market_config.seller_address {
+        Some(seller) if by_dao => seller.clone(),
+        _ => info.sender,
+    };
+    if !UNITS_PROVIDED.has(deps.storage, &provider) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !matches!(auction_phase(deps.as_ref(), &env)?, Phase::Closed {}) {
+        return Err(ContractError::AuctionNotClosed {});
+    }
+    if UNSOLD_RECLAIMED.has(deps.storage, &provider) {
+        return Err(ContractError::AlreadyFinalized {});
+    }
+    // what is unsold is only known once the commitments have cleared
+    let mut events = if market_config.settlement == (Settlement::UniformClearing {}) {
+        ensure_settled(&mut deps, &env)?
+    } else {
+        vec![]
+    };
+
+    let market_state = MARKET_STATE.load(deps.storage)?;
+    let shares = POOL_SHARES
+        .may_load(deps.storage, &provider)?
+        .unwrap_or_default();
+    let total_shares = TOTAL_POOL_SHARES.load(deps.storage)?;
+    let finalized_shares = FINALIZED_SHARES.may_load(deps.storage)?.unwrap_or_default() + shares;
+    let total_reclaimed = TOTAL_UNSOLD_RECLAIMED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    // the last provider to finalize takes whatever rounding left behind
+    let finalized = finalized_shares >= total_shares;
+    let unsold_units = if finalized {
+        market_state
+            .units_available()
+            .saturating_sub(total_reclaimed)
+    } else {
+        market_state
+            .units_available()
+            .multiply_ratio(shares, total_shares)
+    };
+
+    UNSOLD_RECLAIMED.save(deps.storage, &provider, &unsold_units)?;
+    TOTAL_UNSOLD_RECLAIMED.save(deps.storage, &(total_reclaimed + unsold_units))?;
+    FINALIZED_SHARES.save(deps.storage, &finalized_shares)?;
+    FINALIZED.save(deps.storage, &finalized)?;
+
+    let reclaim_msgs = if unsold_units.is_zero() {
+        vec![]
+    } else {
+        vec![market_config
+            .unit_asset
+            .into_send_message(unsold_units, &provider)?]
+    };
+    if by_dao {
+        events.push(record_change(
+            deps.storage,
+            &env,
+            GovernanceChange::Finalize { unsold_units },
+        )?);
+    }
+
+    Ok(Response::new()
+        .add_messages(reclaim_msgs)
+        .add_events(events)
+        .add_attribute("method", "finalize")
+        .add_attribute("provider", provider)
+        .add_attribute("unsold_units", unsold_units.to_string()))
+}
+
+fn cancel_auction(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut market_config = MARKET_CONFIG.load(deps.storage)?;
+    ensure_seller_or_role(
+        deps.storage,
+        &market_config,
+        &info.sender,
+        &Role::Operator {},
+    )?;
+    let seller = market_config
+        .seller_address
+        .clone()
+        .ok_or(ContractError::Unauthorized {})?;
+    let market_state = MARKET_STATE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::AuctionNotScheduled {})?;
+    let total_committed = TOTAL_COMMITTED.may_load(deps.storage)?.unwrap_or_default();
+    if !market_state.units_sold.is_zero() || !total_committed.is_zero() {
+        return Err(ContractError::AuctionHasSales {});
+    }
+
+    MARKET_STATE.remove(deps.storage);
+    UNIX_START.remove(deps.storage);
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut events: Vec<Event> = vec![];
+
+    // every provider gets their units back, anything unaccounted for goes to the seller
+    let providers = UNITS_PROVIDED
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
+    let balance = market_config
+        .unit_asset
+        .query_balance(&deps.querier, &env.contract.address)?;
+    let total_provided = TOTAL_UNITS_PROVIDED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let mut refunds = providers;
+    refunds.push((seller.clone(), balance.saturating_sub(total_provided)));
+
+    for (provider, units) in refunds {
+        UNITS_PROVIDED.remove(deps.storage, &provider);
+        POOL_SHARES.remove(deps.storage, &provider);
+        EARNINGS_CHECKPOINTS.remove(deps.storage, &provider);
+        if units.is_zero() {
+            continue;
+        }
+        msgs.push(
+            market_config
+                .unit_asset
+                .clone()
+                .into_send_message(units, &provider)?,
+        );
+        events.push(
+            Event::new("units_refunded")
+                .add_attribute("recipient", provider.to_string())
+                .add_attribute("units", units.to_string()),
+        );
+    }
+    TOTAL_UNITS_PROVIDED.remove(deps.storage);
+    TOTAL_POOL_SHARES.remove(deps.storage);
+    EARNINGS_INDEX.remove(deps.storage);
+
+    if let Some(seller_nft) = market_config.seller_nft.clone() {
+        msgs.push(
+            wasm_execute(
+                seller_nft.contract_addr.to_string(),
+                &cw721::Cw721ExecuteMsg::TransferNft {
+                    recipient: seller.to_string(),
+                    token_id: seller_nft.token_id.clone(),
+                },
+                vec![],
+            )?
+            .into(),
+        );
+        events.push(
+            Event::new("nft_returned")
+                .add_attribute("recipient", seller.to_string())
+                .add_attribute("contract_addr", seller_nft.contract_addr)
+                .add_attribute("token_id", seller_nft.token_id),
+        );
+        // whoever holds the nft next becomes the seller when they schedule
+        market_config.seller_address = None;
+        MARKET_CONFIG.save(deps.storage, &market_config)?;
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_events(events)
+        .add_attribute("method", "cancel_auction")
+        .add_attribute("seller", seller))
+}
+
+fn reconcile(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    ensure_seller_or_role(
+        deps.storage,
+        &market_config,
+        &info.sender,
+        &Role::Operator {},
+    )?;
+    let seller = market_config
+        .seller_address
+        .clone()
+        .ok_or(ContractError::Unauthorized {})?;
+
+    let ReconciliationResponse { surplus, .. } = query_reconciliation(deps.as_ref(), env.clone())?;
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    if !surplus.is_zero() {
+        match auction_phase(deps.as_ref(), &env)? {
+            Phase::NotScheduled {} => {
+                record_units_provided(deps.storage, &seller, surplus)?;
+            }
+            Phase::Scheduled {} | Phase::Open {} => {
+                record_units_provided(deps.storage, &seller, surplus)?;
+                MARKET_STATE.update(deps.storage, |mut market| -> StdResult<_> {
+                    market.total_units += surplus;
+                    Ok(market)
+                })?;
+            }
+            // too late to sell them, hand them back
+            Phase::Closed {} => {
+                msgs.push(
+                    market_config
+                        .unit_asset
+                        .into_send_message(surplus, &seller)?,
+                );
+            }
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("method", "reconcile")
+        .add_attribute("seller", seller)
+        .add_attribute("surplus", surplus.to_string()))
+}
+
+fn execute_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, &Role::Owner {})?;
+    let address = deps.api.addr_validate(&address)?;
+    grant_role(deps.storage, &address, &role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "grant_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", address))
+}
+
+fn execute_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, &Role::Owner {})?;
+    let address = deps.api.addr_validate(&address)?;
+    revoke_role(deps.storage, &address, &role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "revoke_role")
+        .add_attribute("role", role.key())
+        .add_attribute("address", address))
+}
+
+fn transfer_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, &Role::Owner {})?;
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "transfer_ownership")
+        .add_attribute("owner", info.sender)
+        .add_attribute("pending_owner", new_owner))
+}
+
+fn set_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let events = if is_dao(&market_config, &info.sender) {
+        let change = if paused {
+            GovernanceChange::Pause {}
+        } else {
+            GovernanceChange::Unpause {}
+        };
+        vec![record_change(deps.storage, &env, change)?]
+    } else {
+        ensure_role(deps.storage, &info.sender, &Role::Pauser {})?;
+        vec![]
+    };
+    PAUSED.save(deps.storage, &paused)?;
+
+    let method = if paused { "pause" } else { "unpause" };
+    Ok(Response::new()
+        .add_events(events)
+        .add_attribute("method", method)
+        .add_attribute("pauser", info.sender))
+}
+
+fn update_parameters(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_price: Option<Decimal>,
+    target_duration: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut market_config = MARKET_CONFIG.load(deps.storage)?;
+    ensure_dao(&market_config, &info.sender)?;
+    if !matches!(
+        auction_phase(deps.as_ref(), &env)?,
+        Phase::NotScheduled {} | Phase::Scheduled {}
+    ) {
+        return Err(ContractError::AuctionAlreadyStarted {});
+    }
+
+    let change = GovernanceChange::UpdateParameters {
+        min_price: min_price.unwrap_or(market_config.min_price),
+        target_duration: target_duration.unwrap_or(market_config.target_duration),
+        previous_min_price: market_config.min_price,
+        previous_target_duration: market_config.target_duration,
+    };
+    market_config.min_price = min_price.unwrap_or(market_config.min_price);
+    market_config.target_duration = target_duration.unwrap_or(market_config.target_duration);
+    market_config.validate()?;
+    MARKET_CONFIG.save(deps.storage, &market_config)?;
+    // a scheduled market carries its own copy of the parameters
+    if let Some(mut market) = MARKET_STATE.may_load(deps.storage)? {
+        market.min_price = market_config.min_price;
+        market.target_duration = market_config.target_duration;
+        MARKET_STATE.save(deps.storage, &market)?;
+    }
+    let event = record_change(deps.storage, &env, change)?;
+
+    Ok(Response::new()
+        .add_event(event)
+        .add_attribute("method", "update_parameters")
+        .add_attribute("min_price", market_config.min_price.to_string())
+        .add_attribute("target_duration", market_config.target_duration))
+}
+
+fn update_valset(
+    deps: DepsMut,
+    info: MessageInfo,
+    update: ValsetUpdate,
+) -> Result<Response, ContractError> {
+    ensure_role(deps.storage, &info.sender, &Role::Relayer {})?;
+    if let Some(current) = VALSET.may_load(deps.storage)? {
+        if update.nonce <= current.nonce {
+            return Err(ContractError::StaleValsetNonce {
+                nonce: update.nonce,
+                current: current.nonce,
+            });
+        }
+    }
+    let valset = update.into_checked(deps.api)?;
+    VALSET.save(deps.storage, &valset)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_valset")
+        .add_attribute("nonce", valset.nonce.to_string())
+        .add_attribute("power", valset.power.to_string())
+        .add_attribute("validators", valset.validators.len().to_string()))
+}
+
+fn claim_validator_share(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let accrued = VALIDATOR_SHARES_ACCRUED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    let claimed = VALIDATOR_SHARES_CLAIMED
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+
+    let to_claim = accrued.saturating_sub(claimed);
+    if to_claim.is_zero() {
+        return Err(ContractError::NoValidatorShareToClaim {});
+    }
+    VALIDATOR_SHARES_CLAIMED.save(deps.storage, &info.sender, &accrued)?;
+
+    let claim_msg = market_config
+        .spend_asset
+        .into_send_message(to_claim, &info.sender)?;
+
+    Ok(Response::new()
+        .add_message(claim_msg)
+        .add_attribute("method", "claim_validator_share")
+        .add_attribute("validator", info.sender)
+        .add_attribute("amount", to_claim.to_string()))
+}
+
+fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending_owner = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if pending_owner != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    OWNER.save(deps.storage, &pending_owner)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_ownership")
+        .add_attribute("owner", pending_owner))
 }
 
 /// Handling contract query
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetMarketState {} => to_binary(&query_market_state(deps)?), // Add other QueryMsg variants as needed
+        QueryMsg::GetMarketState {} => to_binary(&query_market_state(deps, env)?),
+        QueryMsg::QuoteBuy { units } => to_binary(&query_quote_buy(deps, env, units)?),
+        QueryMsg::QuoteSpend { amount } => to_binary(&query_quote_spend(deps, env, amount)?),
+        QueryMsg::CurrentPrice {} => to_binary(&query_current_price(deps, env)?),
+        QueryMsg::UnitsProvided { provider } => to_binary(&query_units_provided(deps, provider)?),
+        QueryMsg::ProviderShares { start_after, limit } => {
+            to_binary(&query_provider_shares(deps, start_after, limit)?)
+        }
+        QueryMsg::FeeAccounting { start_after, limit } => {
+            to_binary(&query_fee_accounting(deps, start_after, limit)?)
+        }
+        QueryMsg::Purchases {
+            buyer,
+            start_after,
+            limit,
+        } => to_binary(&query_purchases(deps, buyer, start_after, limit)?),
+        QueryMsg::BuyerSummary { buyer } => to_binary(&query_buyer_summary(deps, buyer)?),
+        QueryMsg::Clearing {} => to_binary(&query_clearing(deps)?),
+        QueryMsg::Commitment { buyer } => to_binary(&query_commitment(deps, buyer)?),
+        QueryMsg::VestingStatus { buyer } => to_binary(&query_vesting_status(deps, env, buyer)?),
+        QueryMsg::EpochStatus {} => to_binary(&query_epoch_status(deps, env)?),
+        QueryMsg::Roles {} => to_binary(&query_roles(deps)?),
+        QueryMsg::SalePhase { address, proof } => {
+            to_binary(&query_sale_phase(deps, env, address, proof)?)
+        }
+        QueryMsg::Reconciliation {} => to_binary(&query_reconciliation(deps, env)?),
+        QueryMsg::CurrentValset {} => to_binary(&query_current_valset(deps)?),
+        QueryMsg::ParameterHistory { start_after, limit } => {
+            to_binary(&query_parameter_history(deps, start_after, limit)?)
+        }
+        QueryMsg::ValidatorShare { validator } => {
+            to_binary(&query_validator_share(deps, validator)?)
+        }
     }
 }
 
-fn query_market_state(deps: Deps) -> Result<MarketStateResponse, StdError> {
+fn query_market_state(deps: Deps, env: Env) -> Result<MarketStateResponse, StdError> {
     let market = MARKET_STATE.may_load(deps.storage)?;
     let unix_start_time = UNIX_START.may_load(deps.storage)?;
     let config = MARKET_CONFIG.load(deps.storage)?;
+    let phase = auction_phase(deps, &env)?;
+    let finalized = FINALIZED.may_load(deps.storage)?.unwrap_or(false);
+    let paused = PAUSED.may_load(deps.storage)?.unwrap_or(false);
 
     Ok(MarketStateResponse {
         market,
         unix_start_time,
         config,
+        phase,
+        finalized,
+        paused,
+    })
+}
+
+/// loads the scheduled market along with the seconds elapsed since it started
+fn load_started_market(deps: Deps, env: &Env) -> Result<(Market, Uint128), StdError> {
+    let market = MARKET_STATE.load(deps.storage)?;
+    let unix_start = UNIX_START.load(deps.storage)?;
+    let time_elapsed = time_elapsed(env, unix_start).map_err(into_std_error)?;
+    Ok((market, time_elapsed))
+}
+
+/// evaluates a purchase of `units` against a copy of the market
+fn quote(
+    market: Market,
+    units: Uint128,
+    time_elapsed: Uint128,
+) -> Result<QuoteResponse, ContractError> {
+    let price = market.calculate_price(units, time_elapsed)?;
+    let mut post_trade_market = market;
+    let Cost(cost) = post_trade_market.buy_units(units, time_elapsed)?;
+    let post_trade_price = post_trade_market.calculate_price(Uint128::one(), time_elapsed)?;
+
+    Ok(QuoteResponse {
+        units,
+        price,
+        cost,
+        post_trade_price,
+    })
+}
+
+fn query_quote_buy(deps: Deps, env: Env, units: Uint128) -> Result<QuoteResponse, StdError> {
+    let (market, time_elapsed) = load_started_market(deps, &env)?;
+    quote(market, units, time_elapsed).map_err(into_std_error)
+}
+
+fn query_quote_spend(deps: Deps, env: Env, amount: Uint128) -> Result<QuoteResponse, StdError> {
+    let (market, time_elapsed) = load_started_market(deps, &env)?;
+    let units = market
+        .calculate_received(amount, time_elapsed)
+        .map_err(into_std_error)?;
+    quote(market, units, time_elapsed).map_err(into_std_error)
+}
+
+fn query_current_price(deps: Deps, env: Env) -> Result<CurrentPriceResponse, StdError> {
+    let (market, time_elapsed) = load_started_market(deps, &env)?;
+    let price = market
+        .calculate_price(Uint128::one(), time_elapsed)
+        .map_err(into_std_error)?;
+
+    Ok(CurrentPriceResponse {
+        price,
+        time_elapsed,
+    })
+}
+
+fn query_units_provided(deps: Deps, provider: String) -> StdResult<UnitsProvidedResponse> {
+    let provider_addr = deps.api.addr_validate(&provider)?;
+    let units = UNITS_PROVIDED
+        .may_load(deps.storage, &provider_addr)?
+        .unwrap_or_default();
+    Ok(UnitsProvidedResponse { provider, units })
+}
+
+/// the units the vault should hold: everything provided before the auction is
+/// scheduled, then whatever is left unsold and not yet reclaimed by providers
+fn accounted_units(deps: Deps) -> StdResult<Uint128> {
+    Ok(match MARKET_STATE.may_load(deps.storage)? {
+        Some(market) => {
+            let reclaimed = TOTAL_UNSOLD_RECLAIMED
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            // cleared units stay in the vault until their buyers claim them
+            let unclaimed = match CLEARING.may_load(deps.storage)? {
+                Some(clearing) => clearing.units.saturating_sub(
+                    COMMITTED_UNITS_CLAIMED
+                        .may_load(deps.storage)?
+                        .unwrap_or_default(),
+                ),
+                None => Uint128::zero(),
+            };
+            let vesting_locked = TOTAL_VESTING_LOCKED
+                .may_load(deps.storage)?
+                .unwrap_or_default();
+            market.units_available().saturating_sub(reclaimed) + unclaimed + vesting_locked
+        }
+        None => TOTAL_UNITS_PROVIDED
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+    })
+}
+
+fn query_provider_shares(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ProviderSharesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let total_units_provided = TOTAL_UNITS_PROVIDED
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    let total_shares = TOTAL_POOL_SHARES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let shares = UNITS_PROVIDED
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (provider, units_provided) = item?;
+            let earnings_withdrawn = EARNINGS_WITHDRAWN
+                .may_load(deps.storage, &provider)?
+                .unwrap_or_default();
+            let unsold_reclaimed = UNSOLD_RECLAIMED
+                .may_load(deps.storage, &provider)?
+                .unwrap_or_default();
+            let pool_shares = POOL_SHARES
+                .may_load(deps.storage, &provider)?
+                .unwrap_or_default();
+            Ok(ProviderShare {
+                provider: provider.to_string(),
+                units_provided,
+                share: Decimal::from_ratio(pool_shares, total_shares),
+                earnings_withdrawn,
+                unsold_reclaimed,
+            })
+        })
+        .collect::<StdResult<Vec<ProviderShare>>>()?;
+
+    Ok(ProviderSharesResponse {
+        total_units_provided,
+        shares,
+    })
+}
+
+fn query_fee_accounting(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeAccountingResponse> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let recipients = FEES_ACCRUED
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (recipient, accrued) = item?;
+            let claimed = FEES_CLAIMED
+                .may_load(deps.storage, &recipient)?
+                .unwrap_or_default();
+            Ok(FeeRecipient {
+                recipient: recipient.to_string(),
+                accrued,
+                claimed,
+            })
+        })
+        .collect::<StdResult<Vec<FeeRecipient>>>()?;
+
+    Ok(FeeAccountingResponse {
+        fees: market_config.fees,
+        referral_bps: market_config.referral_bps,
+        total_accrued: TOTAL_FEES_ACCRUED
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        total_claimed: TOTAL_FEES_CLAIMED
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        recipients,
+    })
+}
+
+fn query_purchases(
+    deps: Deps,
+    buyer: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PurchasesResponse> {
+    let buyer = deps.api.addr_validate(&buyer)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let purchases = purchases()
+        .idx
+        .buyer
+        .prefix(buyer)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (id, purchase) = item?;
+            Ok(PurchaseResponse { id, purchase })
+        })
+        .collect::<StdResult<Vec<PurchaseResponse>>>()?;
+
+    Ok(PurchasesResponse { purchases })
+}
+
+fn query_buyer_summary(deps: Deps, buyer: String) -> StdResult<BuyerSummaryResponse> {
+    let buyer = deps.api.addr_validate(&buyer)?;
+    let totals = BUYER_TOTALS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    let average_price = if totals.units.is_zero() {
+        Decimal::zero()
+    } else {
+        Decimal::from_ratio(totals.cost, totals.units)
+    };
+
+    Ok(BuyerSummaryResponse {
+        buyer: buyer.to_string(),
+        purchase_count: totals.purchase_count,
+        total_units: totals.units,
+        total_cost: totals.cost,
+        average_price,
+    })
+}
+
+fn query_clearing(deps: Deps) -> StdResult<ClearingResponse> {
+    Ok(ClearingResponse {
+        total_committed: TOTAL_COMMITTED.may_load(deps.storage)?.unwrap_or_default(),
+        clearing: CLEARING.may_load(deps.storage)?,
+    })
+}
+
+fn query_commitment(deps: Deps, buyer: String) -> StdResult<CommitmentResponse> {
+    let buyer = deps.api.addr_validate(&buyer)?;
+    let amount = COMMITMENTS
+        .may_load(deps.storage, &buyer)?
+        .unwrap_or_default();
+    Ok(CommitmentResponse {
+        buyer: buyer.to_string(),
+        amount,
+    })
+}
+
+fn query_vesting_status(deps: Deps, env: Env, buyer: String) -> StdResult<VestingStatusResponse> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let buyer = deps.api.addr_validate(&buyer)?;
+    let vesting = VESTING.may_load(deps.storage, &buyer)?.unwrap_or_default();
+    let vested = vested_units(deps, &env, &market_config, &vesting)?;
+
+    Ok(VestingStatusResponse {
+        buyer: buyer.to_string(),
+        total: vesting.total,
+        vested,
+        claimed: vesting.claimed,
+        claimable: vested - vesting.claimed,
+    })
+}
+
+fn query_epoch_status(deps: Deps, env: Env) -> StdResult<EpochStatusResponse> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let release = market_config
+        .epoch_release
+        .ok_or_else(|| into_std_error(ContractError::EpochReleaseNotConfigured {}))?;
+    let market = MARKET_STATE.load(deps.storage)?;
+    let unix_start = UNIX_START.load(deps.storage)?;
+    epoch_status(deps, &env, &release, &market, unix_start)
+}
+
+fn query_reconciliation(deps: Deps, env: Env) -> StdResult<ReconciliationResponse> {
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    let balance = market_config
+        .unit_asset
+        .query_balance(&deps.querier, &env.contract.address)?;
+    let accounted_units = accounted_units(deps)?;
+
+    Ok(ReconciliationResponse {
+        balance,
+        accounted_units,
+        surplus: balance.saturating_sub(accounted_units),
+        deficit: accounted_units.saturating_sub(balance),
+    })
+}
+
+fn query_roles(deps: Deps) -> StdResult<RolesResponse> {
+    Ok(RolesResponse {
+        owner: OWNER.load(deps.storage)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+        operators: role_members(deps.storage, &Role::Operator {})?,
+        pausers: role_members(deps.storage, &Role::Pauser {})?,
+        relayers: role_members(deps.storage, &Role::Relayer {})?,
+    })
+}
+
+fn query_parameter_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ParameterHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let changes = PARAMETER_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<Vec<ParameterChange>>>()?;
+    Ok(ParameterHistoryResponse { changes })
+}
+
+fn query_current_valset(deps: Deps) -> StdResult<CurrentValsetResponse> {
+    Ok(CurrentValsetResponse {
+        valset: VALSET.may_load(deps.storage)?,
+    })
+}
+
+fn query_validator_share(deps: Deps, validator: String) -> StdResult<ValidatorShareResponse> {
+    let validator = deps.api.addr_validate(&validator)?;
+    Ok(ValidatorShareResponse {
+        accrued: VALIDATOR_SHARES_ACCRUED
+            .may_load(deps.storage, &validator)?
+            .unwrap_or_default(),
+        claimed: VALIDATOR_SHARES_CLAIMED
+            .may_load(deps.storage, &validator)?
+            .unwrap_or_default(),
+    })
+}
+
+fn query_sale_phase(
+    deps: Deps,
+    env: Env,
+    address: String,
+    proof: Option<AllowlistProof>,
+) -> StdResult<SalePhaseResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let market_config = MARKET_CONFIG.load(deps.storage)?;
+    if market_config.sale_phases.is_empty() {
+        return Ok(SalePhaseResponse {
+            phase_id: None,
+            phase: None,
+            eligible: true,
+            remaining_allocation: None,
+        });
+    }
+
+    let active = active_phase(
+        &market_config.sale_phases,
+        Uint64::from(env.block.time.seconds()),
+    );
+    let (phase_id, phase) = match active {
+        Some(active) => active,
+        None => {
+            return Ok(SalePhaseResponse {
+                phase_id: None,
+                phase: None,
+                eligible: false,
+                remaining_allocation: None,
+            })
+        }
+    };
+    let (eligible, remaining_allocation) = match &phase.merkle_root {
+        None => (true, None),
+        Some(merkle_root) => {
+            match remaining_allocation(
+                deps.storage,
+                phase_id,
+                merkle_root,
+                &address,
+                proof.as_ref(),
+            ) {
+                Ok(remaining) => (true, Some(remaining)),
+                Err(ContractError::NotAllowlisted {}) => (false, None),
+                Err(err) => return Err(into_std_error(err)),
+            }
+        }
+    };
+
+    Ok(SalePhaseResponse {
+        phase_id: Some(Uint64::new(phase_id)),
+        phase: Some(phase.clone()),
+        eligible,
+        remaining_allocation,
     })
 }
 
+fn into_std_error(err: ContractError) -> StdError {
+    match err {
+        ContractError::Std(err) => err,
+        err => StdError::generic_err(err.to_string()),
+    }
+}
+
 /// Handling submessage reply.
 /// For more info on submessage and reply, see https://github.com/CosmWasm/cosmwasm/blob/main/SEMANTICS.md#submessages
 #[cfg_attr(not(feature = "library"), entry_point)]
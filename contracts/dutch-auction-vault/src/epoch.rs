@@ -1,23 +1,25 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint64;
+use cosmwasm_std::{Uint128, Uint64};
 
 use crate::ContractError;
 
+/// Consecutive periods of `duration` seconds counted from `start_time`, so
+/// the first epoch lasts as long as every other
 #[cw_serde]
 pub struct Epoch {
     pub duration: Uint64,
     pub start_time: Uint64,
-    pub offset: Uint64,
 }
 
 impl Epoch {
     pub fn new(duration: Uint64, start_time: Uint64) -> Self {
-        let offset = start_time % duration;
+        if duration.is_zero() {
+            panic!("epoch duration cannot be zero");
+        }
 
         Epoch {
             duration,
             start_time,
-            offset,
         }
     }
 
@@ -25,8 +27,13 @@ impl Epoch {
         if seconds < self.start_time {
             return Uint64::from(0u64);
         }
-        let adjusted_seconds = seconds - self.start_time + self.offset;
-        adjusted_seconds / self.duration
+        (seconds - self.start_time) / self.duration
+    }
+
+    /// unix time in seconds at which the epoch after the one containing `seconds` starts
+    pub fn next_epoch_start(&self, seconds: Uint64) -> Uint64 {
+        let next_epoch = self.current_epoch(seconds) + Uint64::one();
+        self.start_time + next_epoch * self.duration
     }
 
     pub fn validate(&self) -> Result<(), ContractError> {
@@ -37,12 +44,72 @@ impl Epoch {
     }
 }
 
+/// Releases the supply in equal slices, one per epoch from the auction start.
+/// Only continuous purchases are limited by the release.
+#[cw_serde]
+pub struct EpochRelease {
+    /// the length of an epoch in seconds
+    pub epoch_duration: Uint64,
+    /// the number of epochs the supply is spread over
+    pub epochs: Uint64,
+    /// whether an epoch's unsold allocation carries over to the next epochs
+    pub rollover: bool,
+}
+
+impl EpochRelease {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.epoch_duration.is_zero() {
+            return Err(ContractError::EpochDurationCannotBeZero {});
+        }
+        if self.epochs.is_zero() {
+            return Err(ContractError::ReleaseEpochsCannotBeZero {});
+        }
+        Ok(())
+    }
+
+    pub fn epoch(&self, start_time: Uint64) -> Epoch {
+        Epoch::new(self.epoch_duration, start_time)
+    }
+
+    /// the units released up to and including `epoch`
+    pub fn released(&self, total_units: Uint128, epoch: Uint64) -> Uint128 {
+        let released_epochs = (epoch + Uint64::one()).min(self.epochs);
+        total_units.multiply_ratio(released_epochs.u64(), self.epochs.u64())
+    }
+
+    /// the units released in `epoch` alone
+    pub fn allocation(&self, total_units: Uint128, epoch: Uint64) -> Uint128 {
+        let released_before = match epoch.checked_sub(Uint64::one()) {
+            Ok(previous) => self.released(total_units, previous),
+            Err(_) => Uint128::zero(),
+        };
+        self.released(total_units, epoch) - released_before
+    }
+
+    /// the units that can still be bought in `epoch`, given everything sold so
+    /// far and what was sold in `epoch` itself
+    pub fn remaining(
+        &self,
+        total_units: Uint128,
+        epoch: Uint64,
+        units_sold: Uint128,
+        epoch_units_sold: Uint128,
+    ) -> Uint128 {
+        if self.rollover {
+            self.released(total_units, epoch).saturating_sub(units_sold)
+        } else {
+            self.allocation(total_units, epoch)
+                .saturating_sub(epoch_units_sold)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ContractError;
 
-    use super::Epoch;
-    use cosmwasm_std::Uint64;
+    use super::{Epoch, EpochRelease};
+    use cosmwasm_std::{Uint128, Uint64};
 
     #[test]
     fn test_epoch_new() {
@@ -50,7 +117,6 @@ mod tests {
         epoch.validate().unwrap();
         assert_eq!(epoch.duration, Uint64::from(100u64));
         assert_eq!(epoch.start_time, Uint64::from(50u64));
-        assert_eq!(epoch.offset, Uint64::from(50u64));
     }
 
     #[test]
@@ -75,6 +141,9 @@ mod tests {
         epoch.validate().unwrap();
         let current = epoch.current_epoch(Uint64::from(80u64));
         assert_eq!(current, Uint64::from(0u64));
+        // the first epoch lasts a full duration from the start
+        let current = epoch.current_epoch(Uint64::from(149u64));
+        assert_eq!(current, Uint64::from(0u64));
     }
 
     #[test]
@@ -107,7 +176,6 @@ mod tests {
         let epoch = Epoch {
             duration: Uint64::zero(),
             start_time: Uint64::from(50u64),
-            offset: Uint64::from(50u64),
         };
         epoch.current_epoch(Uint64::from(170u64));
     }
@@ -123,11 +191,91 @@ mod tests {
         let epoch = Epoch {
             duration: Uint64::zero(),
             start_time: Uint64::from(50u64),
-            offset: Uint64::from(50u64),
         };
         assert_eq!(
             epoch.validate().unwrap_err(),
             ContractError::EpochDurationCannotBeZero {}
         );
     }
+
+    #[test]
+    fn test_next_epoch_start() {
+        let epoch = Epoch::new(Uint64::from(100u64), Uint64::from(50u64));
+        assert_eq!(
+            epoch.next_epoch_start(Uint64::from(50u64)),
+            Uint64::from(150u64)
+        );
+        assert_eq!(
+            epoch.next_epoch_start(Uint64::from(149u64)),
+            Uint64::from(150u64)
+        );
+        assert_eq!(
+            epoch.next_epoch_start(Uint64::from(170u64)),
+            Uint64::from(250u64)
+        );
+    }
+
+    fn release(rollover: bool) -> EpochRelease {
+        EpochRelease {
+            epoch_duration: Uint64::from(100u64),
+            epochs: Uint64::from(3u64),
+            rollover,
+        }
+    }
+
+    #[test]
+    fn test_epoch_release_allocation() {
+        let total_units = Uint128::new(1000);
+        let release = release(false);
+        let allocations = (0..4u64)
+            .map(|epoch| release.allocation(total_units, Uint64::from(epoch)))
+            .collect::<Vec<Uint128>>();
+        assert_eq!(
+            allocations,
+            vec![
+                Uint128::new(333),
+                Uint128::new(333),
+                Uint128::new(334),
+                Uint128::zero()
+            ]
+        );
+        assert_eq!(
+            release.released(total_units, Uint64::from(7u64)),
+            total_units
+        );
+    }
+
+    #[test]
+    fn test_epoch_release_remaining() {
+        let total_units = Uint128::new(1000);
+        // nothing sold in the first epoch
+        let units_sold = Uint128::zero();
+        let epoch = Uint64::from(1u64);
+
+        assert_eq!(
+            release(false).remaining(total_units, epoch, units_sold, Uint128::zero()),
+            Uint128::new(333)
+        );
+        assert_eq!(
+            release(true).remaining(total_units, epoch, units_sold, Uint128::zero()),
+            Uint128::new(666)
+        );
+        assert_eq!(
+            release(false).remaining(total_units, epoch, Uint128::new(400), Uint128::new(400)),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn test_epoch_release_validation() {
+        assert!(release(false).validate().is_ok());
+        assert_eq!(
+            EpochRelease {
+                epochs: Uint64::zero(),
+                ..release(false)
+            }
+            .validate(),
+            Err(ContractError::ReleaseEpochsCannotBeZero {})
+        );
+    }
 }
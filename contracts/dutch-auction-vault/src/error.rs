@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use contract_upgrade::UpgradeError;
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use cw_utils::PaymentError;
 
 use thiserror::Error;
@@ -51,6 +52,207 @@ pub enum ContractError {
 
     #[error("Auction start time in the past")]
     AuctionStartTimeInThePast {},
+
+    #[error("Price {price} exceeds max price {max_price}")]
+    MaxPriceExceeded { price: Decimal, max_price: Decimal },
+
+    #[error("Cost {cost} exceeds max cost {max_cost}")]
+    MaxCostExceeded { cost: Uint128, max_cost: Uint128 },
+
+    #[error("Received {units} units, expected at least {min_units}")]
+    MinUnitsNotMet { units: Uint128, min_units: Uint128 },
+
+    #[error("Purchase deadline has passed")]
+    DeadlineExceeded {},
+
+    #[error("Invalid price curve parameters")]
+    InvalidPriceCurve {},
+
+    #[error("Not enough units available")]
+    NotEnoughUnitsAvailable {},
+
+    #[error("Auction is closed")]
+    AuctionClosed {},
+
+    #[error("Auction has not closed yet")]
+    AuctionNotClosed {},
+
+    #[error("Auction already finalized")]
+    AlreadyFinalized {},
+
+    #[error("Auction end time is before the start time")]
+    AuctionEndTimeBeforeStartTime {},
+
+    #[error("Auction has not been scheduled")]
+    AuctionNotScheduled {},
+
+    #[error("Auction cannot be cancelled once units have been sold")]
+    AuctionHasSales {},
+
+    #[error("Spend asset and unit asset must differ")]
+    SpendAssetIsUnitAsset {},
+
+    #[error("Start price cannot be zero")]
+    StartPriceCannotBeZero {},
+
+    #[error("Min price cannot be above the start price")]
+    MinPriceAboveStartPrice {},
+
+    #[error("Target duration cannot be zero")]
+    TargetDurationCannotBeZero {},
+
+    #[error("Min total units cannot be zero")]
+    MinTotalUnitsCannotBeZero {},
+
+    #[error("Auction start time is too far in the future")]
+    AuctionStartTimeTooFarInFuture {},
+
+    #[error("Auction needs at least {min_total_units} units, has {total_units}")]
+    InsufficientSupply {
+        total_units: Uint128,
+        min_total_units: Uint128,
+    },
+
+    #[error("Fee basis points are too high")]
+    FeeBpsTooHigh {},
+
+    #[error("Fee label is too long")]
+    FeeLabelTooLong {},
+
+    #[error("Fee label is too short")]
+    FeeLabelTooShort {},
+
+    #[error("Fee label contains invalid characters")]
+    FeeLabelInvalidCharacters {},
+
+    #[error("Fee label is not lower case")]
+    FeeLabelNotLowerCase {},
+
+    #[error("Too many fees")]
+    TooManyFees {},
+
+    #[error("Total fee basis points are too high")]
+    TotalFeeBpsTooHigh {},
+
+    #[error("Buyer cannot refer themselves")]
+    SelfReferral {},
+
+    #[error("No fees to claim")]
+    NoFeesToClaim {},
+
+    #[error("Not supported by the auction's settlement mode")]
+    WrongSettlement {},
+
+    #[error("No commitment to claim")]
+    NoCommitment {},
+
+    #[error("Commitments already settled")]
+    AlreadySettled {},
+
+    #[error("Release epochs cannot be zero")]
+    ReleaseEpochsCannotBeZero {},
+
+    #[error("No vested units")]
+    NoVesting {},
+
+    #[error("Nothing has vested yet")]
+    NothingVested {},
+
+    #[error("Purchase exceeds the {remaining} units left in this epoch")]
+    EpochAllocationExceeded { remaining: Uint128 },
+
+    #[error("No epoch release schedule is configured")]
+    EpochReleaseNotConfigured {},
+
+    #[error("Purchase limits cannot be zero")]
+    PurchaseLimitCannotBeZero {},
+
+    #[error("Minimum units per transaction is above the maximum")]
+    MinUnitsPerTxAboveMax {},
+
+    #[error("Purchase of {units} units is below the minimum of {min_units_per_tx}")]
+    BelowMinUnitsPerTx {
+        units: Uint128,
+        min_units_per_tx: Uint128,
+    },
+
+    #[error("Purchase of {units} units exceeds the maximum of {max_units_per_tx}")]
+    MaxUnitsPerTxExceeded {
+        units: Uint128,
+        max_units_per_tx: Uint128,
+    },
+
+    #[error("Purchase exceeds the {remaining} units the buyer has left")]
+    MaxUnitsPerBuyerExceeded { remaining: Uint128 },
+
+    #[error("Too many sale phases")]
+    TooManySalePhases {},
+
+    #[error("Sale phases must open in order")]
+    SalePhasesOutOfOrder {},
+
+    #[error("Invalid merkle root")]
+    InvalidMerkleRoot {},
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("No sale phase has opened yet")]
+    NoActiveSalePhase {},
+
+    #[error("Buyer is not on the allowlist for this phase")]
+    NotAllowlisted {},
+
+    #[error("Purchase exceeds the {remaining} units left in the buyer's allocation")]
+    AllowlistAllocationExceeded { remaining: Uint128 },
+
+    #[error("Ownership can only be transferred")]
+    CannotGrantOwnerRole {},
+
+    #[error("Ownership can only be transferred, not revoked")]
+    CannotRevokeOwnerRole {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("{0}")]
+    Upgrade(#[from] UpgradeError),
+
+    #[error("Migration needs an owner when the vault has no seller")]
+    MigrationOwnerRequired {},
+
+    #[error("Valset nonce {nonce} is not newer than the current nonce {current}")]
+    StaleValsetNonce { nonce: Uint128, current: Uint128 },
+
+    #[error("Valset must have at least one validator")]
+    EmptyValset {},
+
+    #[error("Too many validators in the valset")]
+    TooManyValidators {},
+
+    #[error("Validator power cannot be zero")]
+    ValidatorPowerCannotBeZero {},
+
+    #[error("Validator {address} is listed more than once")]
+    DuplicateValidator { address: String },
+
+    #[error("Valset power {power} does not match the validators' total power {total}")]
+    ValsetPowerMismatch { power: Uint128, total: Uint128 },
+
+    #[error("No validator share to claim")]
+    NoValidatorShareToClaim {},
+
+    #[error("Validator share basis points are too high")]
+    ValidatorShareBpsTooHigh {},
+
+    #[error("Auction parameters cannot change once it has started")]
+    AuctionAlreadyStarted {},
+
+    #[error("Purchase limits, sale phases and epoch release cannot be used with uniform clearing")]
+    LimitsUnsupportedWithUniformClearing {},
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
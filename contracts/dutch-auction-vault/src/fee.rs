@@ -0,0 +1,143 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128, Uint64};
+
+use crate::ContractError;
+
+/// the most fees a market can be configured with
+pub const MAX_FEES: usize = 10;
+/// basis points in a whole
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+// Fee structure
+#[cw_serde]
+pub struct Fee {
+    pub bps: Uint64,
+    pub address: Addr,
+    pub label: String,
+}
+
+impl Fee {
+    pub fn new(bps: Uint64, address: Addr, label: String) -> Self {
+        Fee {
+            bps,
+            address,
+            label,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.bps > Uint64::from(BPS_DENOMINATOR) {
+            return Err(ContractError::FeeBpsTooHigh {});
+        }
+        // fee label should be format of twitter handle
+        if self.label.len() > 15 {
+            return Err(ContractError::FeeLabelTooLong {});
+        }
+        if self.label.is_empty() {
+            return Err(ContractError::FeeLabelTooShort {});
+        }
+        // only alphanumeric and underscore allowed
+        if !self.label.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(ContractError::FeeLabelInvalidCharacters {});
+        }
+        // only lowercase allowed
+        if self.label != self.label.to_lowercase() {
+            return Err(ContractError::FeeLabelNotLowerCase {});
+        }
+
+        Ok(())
+    }
+
+    /// the cut of `amount` owed to this fee
+    pub fn amount_of(&self, amount: Uint128) -> Uint128 {
+        bps_of(self.bps, amount)
+    }
+}
+
+/// `bps` basis points of `amount`, rounded down
+pub fn bps_of(bps: Uint64, amount: Uint128) -> Uint128 {
+    amount.multiply_ratio(bps.u64(), BPS_DENOMINATOR)
+}
+
+/// validates each fee and ensures the fees, together with the referral cut,
+/// never take more than the whole of a sale
+pub fn validate_fees(fees: &[Fee], referral_bps: Uint64) -> Result<(), ContractError> {
+    if fees.len() > MAX_FEES {
+        return Err(ContractError::TooManyFees {});
+    }
+
+    let mut total_fee_bps = referral_bps;
+    for fee in fees {
+        fee.validate()?;
+        total_fee_bps += fee.bps;
+    }
+
+    if total_fee_bps > Uint64::from(BPS_DENOMINATOR) {
+        return Err(ContractError::TotalFeeBpsTooHigh {});
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_fee(bps: u64, label: &str) -> Fee {
+        Fee::new(
+            Uint64::from(bps),
+            Addr::unchecked("fee_address"),
+            label.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_fee_validation() {
+        assert!(sample_fee(100, "fee_label").validate().is_ok());
+        assert_eq!(
+            sample_fee(10001, "fee_label").validate(),
+            Err(ContractError::FeeBpsTooHigh {})
+        );
+        assert_eq!(
+            sample_fee(100, "fee_label_too_long").validate(),
+            Err(ContractError::FeeLabelTooLong {})
+        );
+        assert_eq!(
+            sample_fee(100, "").validate(),
+            Err(ContractError::FeeLabelTooShort {})
+        );
+        assert_eq!(
+            sample_fee(100, "fee-label").validate(),
+            Err(ContractError::FeeLabelInvalidCharacters {})
+        );
+        assert_eq!(
+            sample_fee(100, "Fee_Label").validate(),
+            Err(ContractError::FeeLabelNotLowerCase {})
+        );
+    }
+
+    #[test]
+    fn test_validate_fees() {
+        let fees = vec![sample_fee(250, "protocol"), sample_fee(250, "treasury")];
+        assert!(validate_fees(&fees, Uint64::from(100u64)).is_ok());
+        assert_eq!(
+            validate_fees(&fees, Uint64::from(9501u64)),
+            Err(ContractError::TotalFeeBpsTooHigh {})
+        );
+
+        let too_many = (0..=MAX_FEES)
+            .map(|i| sample_fee(1, &format!("fee_label{}", i)))
+            .collect::<Vec<Fee>>();
+        assert_eq!(
+            validate_fees(&too_many, Uint64::zero()),
+            Err(ContractError::TooManyFees {})
+        );
+    }
+
+    #[test]
+    fn test_fee_amount_rounds_down() {
+        let fee = sample_fee(250, "protocol");
+        assert_eq!(fee.amount_of(Uint128::new(1000)), Uint128::new(25));
+        assert_eq!(fee.amount_of(Uint128::new(39)), Uint128::zero());
+    }
+}
@@ -0,0 +1,158 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Env, Event, StdResult, Storage, Uint128, Uint64};
+
+use crate::{
+    state::{MarketConfig, NEXT_PARAMETER_CHANGE_ID, PARAMETER_CHANGES},
+    ContractError,
+};
+
+/// A change made to the auction by a DAO proposal
+#[cw_serde]
+pub enum GovernanceChange {
+    ScheduleAuction {
+        start_time_unix: Uint64,
+    },
+    Pause {},
+    Unpause {},
+    UpdateParameters {
+        min_price: Decimal,
+        target_duration: Uint128,
+        previous_min_price: Decimal,
+        previous_target_duration: Uint128,
+    },
+    /// the seller's unsold units were returned to them
+    Finalize {
+        unsold_units: Uint128,
+    },
+}
+
+impl GovernanceChange {
+    pub fn key(&self) -> &'static str {
+        match self {
+            GovernanceChange::ScheduleAuction { .. } => "schedule_auction",
+            GovernanceChange::Pause {} => "pause",
+            GovernanceChange::Unpause {} => "unpause",
+            GovernanceChange::UpdateParameters { .. } => "update_parameters",
+            GovernanceChange::Finalize { .. } => "finalize",
+        }
+    }
+
+    fn event(&self) -> Event {
+        let event = Event::new("governance_change").add_attribute("change", self.key());
+        match self {
+            GovernanceChange::ScheduleAuction { start_time_unix } => {
+                event.add_attribute("start_time_unix", start_time_unix.to_string())
+            }
+            GovernanceChange::Pause {} | GovernanceChange::Unpause {} => event,
+            GovernanceChange::UpdateParameters {
+                min_price,
+                target_duration,
+                previous_min_price,
+                previous_target_duration,
+            } => event
+                .add_attribute("min_price", min_price.to_string())
+                .add_attribute("target_duration", target_duration.to_string())
+                .add_attribute("previous_min_price", previous_min_price.to_string())
+                .add_attribute(
+                    "previous_target_duration",
+                    previous_target_duration.to_string(),
+                ),
+            GovernanceChange::Finalize { unsold_units } => {
+                event.add_attribute("unsold_units", unsold_units.to_string())
+            }
+        }
+    }
+}
+
+/// an entry in the history of governance changes
+#[cw_serde]
+pub struct ParameterChange {
+    pub id: u64,
+    pub change: GovernanceChange,
+    /// unix time in seconds of the block the change was made in
+    pub time: Uint64,
+    pub height: u64,
+}
+
+/// whether `address` is the DAO governing the auction
+pub fn is_dao(market_config: &MarketConfig, address: &Addr) -> bool {
+    market_config.dao.as_ref() == Some(address)
+}
+
+/// errors unless `address` is the DAO governing the auction
+pub fn ensure_dao(market_config: &MarketConfig, address: &Addr) -> Result<(), ContractError> {
+    if !is_dao(market_config, address) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// appends `change` to the history, returning the event announcing it
+pub fn record_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    change: GovernanceChange,
+) -> StdResult<Event> {
+    let id = NEXT_PARAMETER_CHANGE_ID
+        .may_load(storage)?
+        .unwrap_or_default();
+    NEXT_PARAMETER_CHANGE_ID.save(storage, &(id + 1))?;
+
+    let event = change.event().add_attribute("id", id.to_string());
+    PARAMETER_CHANGES.save(
+        storage,
+        id,
+        &ParameterChange {
+            id,
+            change,
+            time: Uint64::new(env.block.time.seconds()),
+            height: env.block.height,
+        },
+    )?;
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_env, MockStorage};
+    use cosmwasm_std::Order;
+
+    use super::*;
+
+    #[test]
+    fn test_record_change() {
+        let mut storage = MockStorage::new();
+        let env = mock_env();
+
+        let event = record_change(&mut storage, &env, GovernanceChange::Pause {}).unwrap();
+        assert_eq!(
+            event,
+            Event::new("governance_change")
+                .add_attribute("change", "pause")
+                .add_attribute("id", "0")
+        );
+        let event = record_change(
+            &mut storage,
+            &env,
+            GovernanceChange::UpdateParameters {
+                min_price: Decimal::percent(20),
+                target_duration: Uint128::new(200),
+                previous_min_price: Decimal::percent(10),
+                previous_target_duration: Uint128::new(100),
+            },
+        )
+        .unwrap();
+        assert_eq!(event.attributes[1].value, "0.2");
+        assert_eq!(event.attributes.last().unwrap().value, "1");
+
+        let history = PARAMETER_CHANGES
+            .range(&storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, change)| change))
+            .collect::<StdResult<Vec<ParameterChange>>>()
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].change, GovernanceChange::Pause {});
+        assert_eq!(history[1].id, 1);
+        assert_eq!(history[1].time, Uint64::new(env.block.time.seconds()));
+    }
+}
@@ -3,9 +3,16 @@ mod error;
 pub mod msg;
 pub mod state;
 pub use crate::error::ContractError;
+pub mod allowlist;
 pub mod epoch;
+pub mod fee;
 pub mod fungible;
+pub mod governance;
 pub mod market;
+pub mod migrations;
+pub mod pool;
 pub mod roles;
 #[cfg(test)]
 mod tests;
+pub mod valset;
+pub mod vesting;
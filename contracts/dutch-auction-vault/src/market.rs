@@ -1,10 +1,54 @@
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Decimal, StdError, Uint128};
 use std::ops::{Mul, Sub};
 
 use cosmwasm_schema::cw_serde;
 
 use crate::error::ContractError;
 
+/// The shape of the price over the course of the auction.
+/// Every curve is floored at the market's min_price.
+#[cw_serde]
+pub enum PriceCurve {
+    /// start_price * (next_units_sold / expected_units_sold)^2
+    SquaredVolumeError {},
+    /// start_price * (next_units_sold / expected_units_sold)^exponent
+    VolumeError { exponent: u32 },
+    /// falls linearly from start_price to min_price over target_duration
+    LinearDecay {},
+    /// halves every half_life seconds, interpolating linearly within a half life
+    ExponentialDecay { half_life: Uint128 },
+    /// the classic dutch auction clock, which ignores volume and drops the
+    /// price by decrement every interval seconds
+    DutchDecline {
+        interval: Uint128,
+        decrement: Decimal,
+    },
+}
+
+impl Default for PriceCurve {
+    fn default() -> Self {
+        PriceCurve::SquaredVolumeError {}
+    }
+}
+
+impl PriceCurve {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        let valid = match self {
+            PriceCurve::SquaredVolumeError {} | PriceCurve::LinearDecay {} => true,
+            PriceCurve::VolumeError { exponent } => *exponent > 0,
+            PriceCurve::ExponentialDecay { half_life } => !half_life.is_zero(),
+            PriceCurve::DutchDecline {
+                interval,
+                decrement,
+            } => !interval.is_zero() && !decrement.is_zero(),
+        };
+        if !valid {
+            return Err(ContractError::InvalidPriceCurve {});
+        }
+        Ok(())
+    }
+}
+
 #[cw_serde]
 /// Auction Market State
 pub struct Market {
@@ -14,10 +58,25 @@ pub struct Market {
     pub target_duration: Uint128,
     pub total_spent: Uint128,
     pub total_units: Uint128,
+    #[serde(default)]
+    pub price_curve: PriceCurve,
 }
 
 pub struct Cost(pub Uint128);
 
+/// The outcome of settling escrowed commitments at a single price
+#[cw_serde]
+pub struct Clearing {
+    /// the price every committed buyer pays per unit
+    pub price: Decimal,
+    /// the units sold to committed buyers
+    pub units: Uint128,
+    /// the spend_asset paid for those units, the rest of the commitments is refunded
+    pub cost: Uint128,
+    /// the spend_asset committed across every buyer
+    pub committed: Uint128,
+}
+
 impl Market {
     pub fn new(
         start_price: Decimal,
@@ -26,6 +85,7 @@ impl Market {
         target_duration: Uint128,
         total_spent: Uint128,
         total_units: Uint128,
+        price_curve: PriceCurve,
     ) -> Self {
         Market {
             start_price,
@@ -34,6 +94,7 @@ impl Market {
             total_spent,
             target_duration,
             total_units,
+            price_curve,
         }
     }
 
@@ -41,19 +102,83 @@ impl Market {
         &self,
         units: Uint128,
         time_elapsed: Uint128,
+    ) -> Result<Decimal, ContractError> {
+        let price = match &self.price_curve {
+            PriceCurve::SquaredVolumeError {} => self.volume_error_price(units, time_elapsed, 2)?,
+            PriceCurve::VolumeError { exponent } => {
+                self.volume_error_price(units, time_elapsed, *exponent)?
+            }
+            PriceCurve::LinearDecay {} => self.linear_decay_price(time_elapsed),
+            PriceCurve::ExponentialDecay { half_life } => {
+                self.exponential_decay_price(time_elapsed, *half_life)
+            }
+            PriceCurve::DutchDecline {
+                interval,
+                decrement,
+            } => self.dutch_decline_price(time_elapsed, *interval, *decrement)?,
+        };
+
+        Ok(price.max(self.min_price))
+    }
+
+    fn volume_error_price(
+        &self,
+        units: Uint128,
+        time_elapsed: Uint128,
+        exponent: u32,
     ) -> Result<Decimal, ContractError> {
         let expected_units_sold = (time_elapsed * self.total_units) / self.target_duration;
         let ratio = if expected_units_sold > Uint128::zero() {
             let next_units_sold = self.units_sold + units;
             let mkt_vol_err = Decimal::from_ratio(next_units_sold, expected_units_sold);
 
-            mkt_vol_err.pow(2)
+            mkt_vol_err
+                .checked_pow(exponent)
+                .map_err(StdError::overflow)?
         } else {
             Decimal::one()
         };
 
-        let price = self.start_price.mul(ratio);
-        Ok(price.max(self.min_price))
+        let price = self
+            .start_price
+            .checked_mul(ratio)
+            .map_err(StdError::overflow)?;
+        Ok(price)
+    }
+
+    fn linear_decay_price(&self, time_elapsed: Uint128) -> Decimal {
+        if time_elapsed >= self.target_duration || self.start_price <= self.min_price {
+            return self.min_price;
+        }
+        let decline = (self.start_price - self.min_price)
+            .mul(Decimal::from_ratio(time_elapsed, self.target_duration));
+        self.start_price - decline
+    }
+
+    fn exponential_decay_price(&self, time_elapsed: Uint128, half_life: Uint128) -> Decimal {
+        let halvings = (time_elapsed / half_life).u128();
+        if halvings >= 128 {
+            return Decimal::zero();
+        }
+        let half_life_price = self
+            .start_price
+            .mul(Decimal::from_ratio(1u128, 1u128 << halvings));
+        let progress = Decimal::from_ratio(time_elapsed % half_life, half_life);
+        half_life_price - half_life_price.mul(Decimal::percent(50)).mul(progress)
+    }
+
+    fn dutch_decline_price(
+        &self,
+        time_elapsed: Uint128,
+        interval: Uint128,
+        decrement: Decimal,
+    ) -> Result<Decimal, ContractError> {
+        let steps = Decimal::from_ratio(time_elapsed / interval, Uint128::one());
+        let decline = decrement.checked_mul(steps).map_err(StdError::overflow)?;
+        if decline >= self.start_price {
+            return Ok(Decimal::zero());
+        }
+        Ok(self.start_price - decline)
     }
 
     pub fn calculate_received(
@@ -61,9 +186,10 @@ impl Market {
         amount: Uint128,
         time_elapsed: Uint128,
     ) -> Result<Uint128, ContractError> {
-        let available_units = self.total_units - self.units_sold;
+        // the upper bound is one past the supply, so it can all be bought
+        let available_units = self.units_available();
         let mut last_units_below = Uint128::zero();
-        let mut last_units_above = available_units;
+        let mut last_units_above = available_units + Uint128::one();
 
         loop {
             let range = last_units_above.sub(last_units_below);
@@ -101,16 +227,59 @@ impl Market {
         Ok(Cost(cost))
     }
 
+    pub fn units_available(&self) -> Uint128 {
+        self.total_units.saturating_sub(self.units_sold)
+    }
+
+    pub fn is_sold_out(&self) -> bool {
+        self.units_available().is_zero()
+    }
+
     pub fn buy_units(
         &mut self,
         units: Uint128,
         time_elapsed: Uint128,
     ) -> Result<Cost, ContractError> {
+        if units > self.units_available() {
+            return Err(ContractError::NotEnoughUnitsAvailable {});
+        }
         let Cost(cost) = self.calculate_cost(units, time_elapsed)?;
         self.total_spent += cost;
         self.units_sold += units;
         Ok(Cost(cost))
     }
+
+    /// whether `amount` is enough to buy every unit still available
+    pub fn clears_supply(
+        &self,
+        amount: Uint128,
+        time_elapsed: Uint128,
+    ) -> Result<bool, ContractError> {
+        let Cost(cost) = self.calculate_cost(self.units_available(), time_elapsed)?;
+        Ok(cost <= amount)
+    }
+
+    /// sells as many units as `committed` buys at a single price, capped at
+    /// the units still available
+    pub fn clear_commitments(
+        &mut self,
+        committed: Uint128,
+        time_elapsed: Uint128,
+    ) -> Result<Clearing, ContractError> {
+        let units = if self.clears_supply(committed, time_elapsed)? {
+            self.units_available()
+        } else {
+            self.calculate_received(committed, time_elapsed)?
+        };
+        let price = self.calculate_price(units, time_elapsed)?;
+        let Cost(cost) = self.buy_units(units, time_elapsed)?;
+        Ok(Clearing {
+            price,
+            units,
+            cost,
+            committed,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -129,6 +298,7 @@ mod tests {
             Uint128::from(86400u128),
             Uint128::from(0u128),
             Uint128::from(1000u128),
+            PriceCurve::default(),
         )
     }
 
@@ -185,6 +355,7 @@ mod tests {
             Uint128::from(3600u128),
             Uint128::from(0u128),
             Uint128::from(1000u128),
+            PriceCurve::default(),
         );
 
         assert_eq!(market.start_price, Decimal::from_str("1.0").unwrap());
@@ -203,6 +374,7 @@ mod tests {
             Uint128::from(3600u128),
             Uint128::from(0u128),
             Uint128::from(1000u128),
+            PriceCurve::default(),
         );
 
         let units = Uint128::new(1000);
@@ -219,6 +391,7 @@ mod tests {
             Uint128::from(3600u128),
             Uint128::from(0u128),
             Uint128::from(1000u128),
+            PriceCurve::default(),
         );
 
         let amount = Uint128::from(1000u128);
@@ -249,6 +422,7 @@ mod tests {
             Uint128::from(1000u128),
             Uint128::from(0u128),
             Uint128::from(1000u128),
+            PriceCurve::default(),
         );
 
         let mut time_elapsed = Uint128::from(1u128);
@@ -307,4 +481,244 @@ mod tests {
             Decimal::from_str("11510.224489795918367285").unwrap()
         );
     }
+
+    #[test]
+    fn test_buy_units_sells_out() {
+        let mut market = create_test_market();
+        assert!(!market.is_sold_out());
+
+        market
+            .buy_units(Uint128::from(600u128), Uint128::zero())
+            .unwrap();
+        assert_eq!(market.units_available(), Uint128::from(400u128));
+
+        assert!(matches!(
+            market.buy_units(Uint128::from(401u128), Uint128::zero()),
+            Err(ContractError::NotEnoughUnitsAvailable {})
+        ));
+
+        market
+            .buy_units(Uint128::from(400u128), Uint128::zero())
+            .unwrap();
+        assert!(market.is_sold_out());
+    }
+
+    fn create_curve_market(price_curve: PriceCurve) -> Market {
+        Market::new(
+            Decimal::from_str("1.0").unwrap(),
+            Decimal::from_str("0.1").unwrap(),
+            Uint128::zero(),
+            Uint128::from(1000u128),
+            Uint128::zero(),
+            Uint128::from(1000u128),
+            price_curve,
+        )
+    }
+
+    fn prices_over_time(market: &Market, units: u128, times: &[u128]) -> Vec<Decimal> {
+        times
+            .iter()
+            .map(|t| {
+                market
+                    .calculate_price(Uint128::new(units), Uint128::new(*t))
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    fn assert_non_increasing(prices: &[Decimal]) {
+        for pair in prices.windows(2) {
+            assert!(pair[0] >= pair[1], "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    const TIMES: [u128; 9] = [0, 1, 10, 50, 100, 250, 500, 1000, 5000];
+
+    #[test]
+    fn test_default_price_curve_is_squared_volume_error() {
+        let squared = create_curve_market(PriceCurve::default());
+        let exponent = create_curve_market(PriceCurve::VolumeError { exponent: 2 });
+
+        for units in [1u128, 10, 100, 500] {
+            assert_eq!(
+                prices_over_time(&squared, units, &TIMES),
+                prices_over_time(&exponent, units, &TIMES)
+            );
+        }
+    }
+
+    #[test]
+    fn test_volume_error_curve_monotonicity_and_floor() {
+        for exponent in [1u32, 2, 3] {
+            let market = create_curve_market(PriceCurve::VolumeError { exponent });
+
+            // for a fixed purchase, the price falls as time passes
+            assert_non_increasing(&prices_over_time(&market, 100, &TIMES[1..]));
+
+            // at a fixed time, larger purchases are never cheaper per unit
+            let prices: Vec<Decimal> = [1u128, 10, 100, 500, 1000]
+                .iter()
+                .map(|units| {
+                    market
+                        .calculate_price(Uint128::new(*units), Uint128::new(500))
+                        .unwrap()
+                })
+                .collect();
+            let mut reversed = prices.clone();
+            reversed.reverse();
+            assert_non_increasing(&reversed);
+
+            // far behind schedule the price bottoms out at min_price
+            assert_eq!(
+                market
+                    .calculate_price(Uint128::one(), Uint128::new(1000))
+                    .unwrap(),
+                market.min_price
+            );
+        }
+    }
+
+    #[test]
+    fn test_volume_error_curve_overflow() {
+        let market = create_curve_market(PriceCurve::VolumeError { exponent: 20 });
+        assert!(market
+            .calculate_price(Uint128::new(1000), Uint128::one())
+            .is_err());
+    }
+
+    #[test]
+    fn test_linear_decay_curve() {
+        let market = create_curve_market(PriceCurve::LinearDecay {});
+        let prices = prices_over_time(&market, 100, &TIMES);
+        assert_non_increasing(&prices);
+
+        assert_eq!(prices[0], Decimal::from_str("1.0").unwrap());
+        // 500 / 1000 of the way from 1.0 to 0.1
+        assert_eq!(prices[6], Decimal::from_str("0.55").unwrap());
+        assert_eq!(prices[7], market.min_price);
+        assert_eq!(prices[8], market.min_price);
+
+        // volume does not move a time-only curve
+        assert_eq!(prices_over_time(&market, 1000, &TIMES), prices);
+    }
+
+    #[test]
+    fn test_exponential_decay_curve() {
+        let market = create_curve_market(PriceCurve::ExponentialDecay {
+            half_life: Uint128::new(100),
+        });
+        let prices = prices_over_time(&market, 100, &TIMES);
+        assert_non_increasing(&prices);
+
+        assert_eq!(prices[0], Decimal::from_str("1.0").unwrap());
+        assert_eq!(prices[3], Decimal::from_str("0.75").unwrap());
+        assert_eq!(prices[4], Decimal::from_str("0.5").unwrap());
+        assert_eq!(prices[5], Decimal::from_str("0.1875").unwrap());
+        // 0.03125 is below the floor
+        assert_eq!(prices[6], market.min_price);
+        assert_eq!(prices[8], market.min_price);
+    }
+
+    #[test]
+    fn test_dutch_decline_curve() {
+        let market = create_curve_market(PriceCurve::DutchDecline {
+            interval: Uint128::new(10),
+            decrement: Decimal::from_str("0.01").unwrap(),
+        });
+        let prices = prices_over_time(&market, 100, &TIMES);
+        assert_non_increasing(&prices);
+
+        assert_eq!(prices[0], Decimal::from_str("1.0").unwrap());
+        // no full interval has passed yet
+        assert_eq!(prices[1], Decimal::from_str("1.0").unwrap());
+        assert_eq!(prices[2], Decimal::from_str("0.99").unwrap());
+        assert_eq!(prices[4], Decimal::from_str("0.9").unwrap());
+        assert_eq!(prices[7], market.min_price);
+        assert_eq!(prices[8], market.min_price);
+    }
+
+    #[test]
+    fn test_clear_commitments() {
+        let mut market = create_test_market();
+        market.price_curve = PriceCurve::LinearDecay {};
+        let closed = market.target_duration;
+
+        // undersubscribed, every committed token buys units at the floor
+        let clearing = market
+            .clone()
+            .clear_commitments(Uint128::new(50), closed)
+            .unwrap();
+        assert_eq!(clearing.price, market.min_price);
+        assert_eq!(clearing.units, Uint128::new(500));
+        assert_eq!(clearing.cost, Uint128::new(50));
+
+        // oversubscribed, the whole supply sells and the excess is left to refund
+        assert!(market.clears_supply(Uint128::new(200), closed).unwrap());
+        let clearing = market.clear_commitments(Uint128::new(200), closed).unwrap();
+        assert_eq!(clearing.units, market.total_units);
+        assert_eq!(clearing.cost, Uint128::new(100));
+        assert!(market.is_sold_out());
+        assert_eq!(market.total_spent, Uint128::new(100));
+    }
+
+    #[test]
+    fn test_price_curve_validation() {
+        assert!(PriceCurve::default().validate().is_ok());
+        assert!(PriceCurve::LinearDecay {}.validate().is_ok());
+        assert!(PriceCurve::VolumeError { exponent: 3 }.validate().is_ok());
+        assert_eq!(
+            PriceCurve::VolumeError { exponent: 0 }.validate(),
+            Err(ContractError::InvalidPriceCurve {})
+        );
+        assert_eq!(
+            PriceCurve::ExponentialDecay {
+                half_life: Uint128::zero()
+            }
+            .validate(),
+            Err(ContractError::InvalidPriceCurve {})
+        );
+        assert_eq!(
+            PriceCurve::DutchDecline {
+                interval: Uint128::zero(),
+                decrement: Decimal::percent(1)
+            }
+            .validate(),
+            Err(ContractError::InvalidPriceCurve {})
+        );
+        assert_eq!(
+            PriceCurve::DutchDecline {
+                interval: Uint128::one(),
+                decrement: Decimal::zero()
+            }
+            .validate(),
+            Err(ContractError::InvalidPriceCurve {})
+        );
+    }
+
+    #[test]
+    fn test_calculate_received_buys_out_supply() {
+        let market = Market::new(
+            Decimal::one(),
+            Decimal::percent(10),
+            Uint128::new(900),
+            Uint128::new(100),
+            Uint128::zero(),
+            Uint128::new(1000),
+            PriceCurve::default(),
+        );
+        let time_elapsed = Uint128::new(50);
+        let Cost(cost) = market
+            .calculate_cost(Uint128::new(100), time_elapsed)
+            .unwrap();
+
+        assert_eq!(
+            market.calculate_received(cost, time_elapsed),
+            Ok(Uint128::new(100))
+        );
+        // more than enough still buys no more than the supply
+        assert_eq!(
+            market.calculate_received(cost * Uint128::new(10), time_elapsed),
+            Ok(Uint128::new(100))
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Storage, Uint128, Uint64};
+use cw_storage_plus::Item;
+use semver::Version;
+
+use crate::{
+    contract::MIN_TOTAL_UNITS,
+    fungible::CheckedFungibleAsset,
+    state::{
+        CheckedNft, MarketConfig, Settlement, EARNINGS_WITHDRAWN, MARKET_CONFIG, MARKET_STATE,
+        OWNER, POOL_SHARES, TOTAL_POOL_SHARES, TOTAL_UNITS_PROVIDED, TOTAL_WITHDRAWN,
+        UNITS_PROVIDED,
+    },
+    ContractError,
+};
+
+/// MarketConfig as stored by 0.1.x
+#[cw_serde]
+pub struct MarketConfigV0_1 {
+    pub seller_address: Option<Addr>,
+    pub seller_nft: Option<CheckedNft>,
+    pub spend_asset: CheckedFungibleAsset,
+    pub unit_asset: CheckedFungibleAsset,
+    pub start_price: Decimal,
+    pub min_price: Decimal,
+    pub target_duration: Uint128,
+}
+
+pub const MARKET_CONFIG_V0_1: Item<MarketConfigV0_1> = Item::new("market_config");
+
+impl From<MarketConfigV0_1> for MarketConfig {
+    fn from(config: MarketConfigV0_1) -> Self {
+        MarketConfig {
+            seller_address: config.seller_address,
+            seller_nft: config.seller_nft,
+            spend_asset: config.spend_asset,
+            unit_asset: config.unit_asset,
+            start_price: config.start_price,
+            min_price: config.min_price,
+            target_duration: config.target_duration,
+            price_curve: Default::default(),
+            end_time: None,
+            fees: vec![],
+            referral_bps: Uint64::zero(),
+            validator_share_bps: Uint64::zero(),
+            settlement: Settlement::default(),
+            vesting: None,
+            epoch_release: None,
+            max_units_per_buyer: None,
+            max_units_per_tx: None,
+            min_units_per_tx: None,
+            min_total_units: MIN_TOTAL_UNITS,
+            sale_phases: vec![],
+            dao: None,
+        }
+    }
+}
+
+/// the first version with the current state layout
+pub fn v0_2() -> Version {
+    Version::new(0, 2, 0)
+}
+
+/// moves state written by 0.1.x into the current layout. the owner defaults
+/// to the seller, and since 0.1.x sold the seller's units alone, the seller
+/// becomes the sole provider of a scheduled auction
+pub fn migrate_from_v0_1(
+    storage: &mut dyn Storage,
+    owner: Option<Addr>,
+) -> Result<(), ContractError> {
+    let config: MarketConfig = MARKET_CONFIG_V0_1.load(storage)?.into();
+    let owner = owner
+        .or_else(|| config.seller_address.clone())
+        .ok_or(ContractError::MigrationOwnerRequired {})?;
+    OWNER.save(storage, &owner)?;
+
+    if let (Some(market), Some(seller)) = (MARKET_STATE.may_load(storage)?, &config.seller_address)
+    {
+        if TOTAL_UNITS_PROVIDED.may_load(storage)?.is_none() {
+            UNITS_PROVIDED.save(storage, seller, &market.total_units)?;
+            TOTAL_UNITS_PROVIDED.save(storage, &market.total_units)?;
+            POOL_SHARES.save(storage, seller, &market.total_units)?;
+            TOTAL_POOL_SHARES.save(storage, &market.total_units)?;
+            let withdrawn = TOTAL_WITHDRAWN.may_load(storage)?.unwrap_or_default();
+            EARNINGS_WITHDRAWN.save(storage, seller, &withdrawn)?;
+        }
+        // rewrites the market with its price curve
+        MARKET_STATE.save(storage, &market)?;
+    }
+
+    MARKET_CONFIG.save(storage, &config)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+    use crate::market::{Market, PriceCurve};
+
+    fn legacy_config(seller_address: Option<Addr>) -> MarketConfigV0_1 {
+        MarketConfigV0_1 {
+            seller_address,
+            seller_nft: None,
+            spend_asset: CheckedFungibleAsset::Native {
+                denom: "spend_asset".to_string(),
+            },
+            unit_asset: CheckedFungibleAsset::Native {
+                denom: "unit_asset".to_string(),
+            },
+            start_price: Decimal::one(),
+            min_price: Decimal::percent(10),
+            target_duration: Uint128::new(100),
+        }
+    }
+
+    #[test]
+    fn test_migrate_scheduled_auction_from_v0_1() {
+        let mut storage = MockStorage::new();
+        let seller = Addr::unchecked("seller");
+        MARKET_CONFIG_V0_1
+            .save(&mut storage, &legacy_config(Some(seller.clone())))
+            .unwrap();
+        let market = Market::new(
+            Decimal::one(),
+            Decimal::percent(10),
+            Uint128::new(400),
+            Uint128::new(100),
+            Uint128::new(300),
+            Uint128::new(1000),
+            PriceCurve::default(),
+        );
+        MARKET_STATE.save(&mut storage, &market).unwrap();
+        TOTAL_WITHDRAWN
+            .save(&mut storage, &Uint128::new(200))
+            .unwrap();
+
+        migrate_from_v0_1(&mut storage, None).unwrap();
+
+        assert_eq!(OWNER.load(&storage), Ok(seller.clone()));
+        assert_eq!(
+            MARKET_CONFIG.load(&storage),
+            Ok(legacy_config(Some(seller.clone())).into())
+        );
+        assert_eq!(
+            UNITS_PROVIDED.load(&storage, &seller),
+            Ok(Uint128::new(1000))
+        );
+        assert_eq!(TOTAL_UNITS_PROVIDED.load(&storage), Ok(Uint128::new(1000)));
+        assert_eq!(POOL_SHARES.load(&storage, &seller), Ok(Uint128::new(1000)));
+        assert_eq!(
+            EARNINGS_WITHDRAWN.load(&storage, &seller),
+            Ok(Uint128::new(200))
+        );
+    }
+
+    #[test]
+    fn test_migrate_requires_an_owner_without_a_seller() {
+        let mut storage = MockStorage::new();
+        MARKET_CONFIG_V0_1
+            .save(&mut storage, &legacy_config(None))
+            .unwrap();
+
+        assert_eq!(
+            migrate_from_v0_1(&mut storage, None),
+            Err(ContractError::MigrationOwnerRequired {})
+        );
+        let owner = Addr::unchecked("owner");
+        migrate_from_v0_1(&mut storage, Some(owner.clone())).unwrap();
+        assert_eq!(OWNER.load(&storage), Ok(owner));
+    }
+}
@@ -1,9 +1,18 @@
-use std::collections::HashMap;
-
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Decimal, Uint128, Uint64};
+use cosmwasm_std::{Addr, Decimal, Uint128, Uint64};
 
-use crate::{fungible::FungibleAsset, market::Market, state::MarketConfig};
+use crate::{
+    allowlist::{AllowlistProof, SalePhase},
+    epoch::EpochRelease,
+    fee::Fee,
+    fungible::FungibleAsset,
+    governance::ParameterChange,
+    market::{Clearing, Market, PriceCurve},
+    roles::Role,
+    state::{MarketConfig, Phase, Purchase, Settlement},
+    valset::{ValidatorPower, Valset},
+    vesting::VestingSchedule,
+};
 
 #[cw_serde]
 pub struct UncheckedNft {
@@ -11,12 +20,20 @@ pub struct UncheckedNft {
     pub token_id: String,
 }
 
+#[cw_serde]
+pub struct FeeUnverified {
+    pub bps: Uint64,
+    pub address: String,
+    pub label: String,
+}
+
+/// A new validator set, applied only if its nonce is newer than the current one
 #[cw_serde]
 pub struct ValsetUpdate {
     pub nonce: Uint128,
+    /// the combined power of every validator in the set
     pub power: Uint128,
-    #[serde(flatten)]
-    pub valset: HashMap<String, Uint128>,
+    pub valset: Vec<ValidatorPower>,
 }
 
 /// Message type for `instantiate` entry_point
@@ -36,6 +53,46 @@ pub struct InstantiateMsg {
     pub seller_address: Option<String>,
     /// the NFT to be sold or None if the seller is an address
     pub seller_nft: Option<UncheckedNft>,
+    /// the shape of the price curve, defaults to the squared volume error curve
+    pub price_curve: Option<PriceCurve>,
+    /// unix time in seconds at which the auction closes, defaults to the
+    /// start time plus target_duration
+    pub end_time: Option<Uint64>,
+    /// cuts of every sale paid out of the proceeds
+    #[serde(default)]
+    pub fees: Vec<FeeUnverified>,
+    /// basis points of a sale earned by the referrer named in the purchase
+    pub referral_bps: Option<Uint64>,
+    /// basis points of every earnings withdrawal distributed across the
+    /// relayed validator set. defaults to zero, which leaves the providers
+    /// every withdrawal
+    pub validator_share_bps: Option<Uint64>,
+    /// how purchases are priced, defaults to continuous settlement. uniform
+    /// clearing cannot be combined with purchase limits, sale phases or an
+    /// epoch release
+    pub settlement: Option<Settlement>,
+    /// locks purchased units under a cliff and linear release, or sends them
+    /// right away when unset
+    pub vesting: Option<VestingSchedule>,
+    /// releases the supply in equal slices per epoch, or all at once when unset
+    pub epoch_release: Option<EpochRelease>,
+    /// the most units a single address can buy across every purchase
+    pub max_units_per_buyer: Option<Uint128>,
+    /// the most units a single purchase can buy
+    pub max_units_per_tx: Option<Uint128>,
+    /// the fewest units a single purchase can buy
+    pub min_units_per_tx: Option<Uint128>,
+    /// the smallest supply, in raw units, the auction can be scheduled with.
+    /// defaults to 1000
+    pub min_total_units: Option<Uint128>,
+    /// phases the sale moves through, optionally gated by an allowlist
+    #[serde(default)]
+    pub sale_phases: Vec<SalePhase>,
+    /// the owner of the vault, defaults to the instantiating address
+    pub owner: Option<String>,
+    /// the DAO whose executed proposals can schedule, pause, tune and
+    /// finalize the auction
+    pub dao: Option<String>,
 }
 
 /// Message type for `execute` entry_point
@@ -49,8 +106,48 @@ pub enum ExecuteMsg {
     ReceiveNft(cw721::Cw721ReceiveMsg),
     /// Used to schedule auction when seller is an address
     ScheduleAuction { start_time_unix: Uint64 },
-    /// Withdraws seller's earnings from the auction
+    /// Withdraws the sender's pro-rata share of the auction earnings
     WithdrawEarnings {},
+    /// Returns the sender's pro-rata share of unsold units once the auction has closed
+    Finalize {},
+    /// Cancels a scheduled auction before any units are sold, returning the
+    /// provided units to their providers and the seller NFT to the seller
+    CancelAuction {},
+    /// Accounts for units sent to the vault without ProvideUnits by crediting
+    /// them to the seller, or returning them if the auction has closed
+    Reconcile {},
+    /// Pays out the fees accrued to the sender
+    ClaimFees {},
+    /// Settles the commitments of a closed uniform clearing auction
+    Settle {},
+    /// Sends the sender their cleared units and refunds the rest of their
+    /// commitment, settling the auction first if needed
+    ClaimCommitment {},
+    /// Sends the sender the units released to them under the vesting schedule
+    ClaimVested {},
+    /// Grants a role to an address, callable by the owner
+    GrantRole { role: Role, address: String },
+    /// Revokes a role from an address, callable by the owner
+    RevokeRole { role: Role, address: String },
+    /// Offers ownership to `new_owner`, who takes over once they accept
+    TransferOwnership { new_owner: String },
+    /// Accepts ownership offered to the sender
+    AcceptOwnership {},
+    /// Halts purchases, commitments and scheduling, callable by a pauser.
+    /// refunds, claims and withdrawals keep working
+    Pause {},
+    /// Resumes a paused vault, callable by a pauser
+    Unpause {},
+    /// Replaces the validator set earnings are distributed across, callable
+    /// by a relayer
+    UpdateValset(ValsetUpdate),
+    /// Pays out the earnings distributed to the sender as a validator
+    ClaimValidatorShare {},
+    /// Changes the auction parameters before it starts, callable by the DAO
+    UpdateParameters {
+        min_price: Option<Decimal>,
+        target_duration: Option<Uint128>,
+    },
 }
 
 // Receive Action
@@ -61,7 +158,34 @@ pub enum ReceiveMsg {
     ProvideUnits {},
     /// Send funds to the contract to buy a requested amount of units.
     /// if more than the requested amount is sent, the remainder is returned.
-    BuyUnits { units: Uint128 },
+    BuyUnits {
+        units: Uint128,
+        /// the highest price per unit the buyer is willing to pay
+        max_price: Option<Decimal>,
+        /// the highest total cost the buyer is willing to pay
+        max_cost: Option<Uint128>,
+        /// unix time in seconds after which the purchase is rejected
+        deadline: Option<Uint64>,
+        /// the address that referred the buyer, earning the referral cut
+        referrer: Option<String>,
+        /// proves the buyer is allowlisted when the active phase is gated
+        proof: Option<AllowlistProof>,
+    },
+    /// Spend all of the funds sent on as many units as they can buy.
+    /// any amount left over from rounding is returned.
+    BuyWithAmount {
+        /// the fewest units the buyer is willing to receive
+        min_units: Uint128,
+        /// unix time in seconds after which the purchase is rejected
+        deadline: Option<Uint64>,
+        /// the address that referred the buyer, earning the referral cut
+        referrer: Option<String>,
+        /// proves the buyer is allowlisted when the active phase is gated
+        proof: Option<AllowlistProof>,
+    },
+    /// Escrows the funds sent as a commitment to buy at the clearing price.
+    /// only available under uniform clearing settlement
+    Commit {},
 }
 
 #[cw_serde]
@@ -71,7 +195,11 @@ pub enum ReceiveNftMsg {
 
 /// Message type for `migrate` entry_point
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    /// the owner to set when migrating from a version without roles,
+    /// defaults to the seller
+    pub owner: Option<String>,
+}
 
 /// Message type for `query` entry_point
 #[cw_serde]
@@ -79,6 +207,79 @@ pub enum MigrateMsg {}
 pub enum QueryMsg {
     #[returns(MarketStateResponse)]
     GetMarketState {},
+    /// Quotes the cost of buying `units` at the current block time
+    #[returns(QuoteResponse)]
+    QuoteBuy { units: Uint128 },
+    /// Quotes how many units `amount` of spend_asset buys at the current block time
+    #[returns(QuoteResponse)]
+    QuoteSpend { amount: Uint128 },
+    /// Returns the price of the next unit at the current block time
+    #[returns(CurrentPriceResponse)]
+    CurrentPrice {},
+    /// Returns the units a provider has provided for sale
+    #[returns(UnitsProvidedResponse)]
+    UnitsProvided { provider: String },
+    /// Compares the vault's unit_asset balance with the units it accounts for
+    #[returns(ReconciliationResponse)]
+    Reconciliation {},
+    /// Lists every provider's share of the pooled units
+    #[returns(ProviderSharesResponse)]
+    ProviderShares {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the fee configuration along with the fees accrued and claimed
+    /// by each recipient
+    #[returns(FeeAccountingResponse)]
+    FeeAccounting {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists a buyer's purchases, oldest first
+    #[returns(PurchasesResponse)]
+    Purchases {
+        buyer: String,
+        /// the purchase id to start after
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the totals of every purchase a buyer has made
+    #[returns(BuyerSummaryResponse)]
+    BuyerSummary { buyer: String },
+    /// Returns the commitments escrowed so far and, once settled, the clearing
+    #[returns(ClearingResponse)]
+    Clearing {},
+    /// Returns the spend_asset a buyer has committed and not yet claimed
+    #[returns(CommitmentResponse)]
+    Commitment { buyer: String },
+    /// Returns how much of a buyer's units have vested and been claimed
+    #[returns(VestingStatusResponse)]
+    VestingStatus { buyer: String },
+    /// Returns where the epoch release schedule stands
+    #[returns(EpochStatusResponse)]
+    EpochStatus {},
+    /// Returns the owner and every address granted a role
+    #[returns(RolesResponse)]
+    Roles {},
+    /// Returns the active sale phase and whether `address` can buy in it
+    #[returns(SalePhaseResponse)]
+    SalePhase {
+        address: String,
+        proof: Option<AllowlistProof>,
+    },
+    /// Lists the changes made by the DAO, oldest first
+    #[returns(ParameterHistoryResponse)]
+    ParameterHistory {
+        /// the change id to start after
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns the validator set earnings are distributed across
+    #[returns(CurrentValsetResponse)]
+    CurrentValset {},
+    /// Returns the earnings distributed to a validator and how much it has claimed
+    #[returns(ValidatorShareResponse)]
+    ValidatorShare { validator: String },
 }
 
 // We define a custom struct for each query response
@@ -87,4 +288,175 @@ pub struct MarketStateResponse {
     pub market: Option<Market>,
     pub unix_start_time: Option<Uint64>,
     pub config: MarketConfig,
+    pub phase: Phase,
+    /// whether every provider has reclaimed their unsold units
+    pub finalized: bool,
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct QuoteResponse {
+    /// the number of units bought
+    pub units: Uint128,
+    /// the price per unit paid for the purchase
+    pub price: Decimal,
+    /// the total amount of spend_asset required
+    pub cost: Uint128,
+    /// the price of the next unit once the purchase has been made
+    pub post_trade_price: Decimal,
+}
+
+#[cw_serde]
+pub struct CurrentPriceResponse {
+    /// the price of the next unit
+    pub price: Decimal,
+    /// seconds elapsed since the auction started
+    pub time_elapsed: Uint128,
+}
+
+#[cw_serde]
+pub struct UnitsProvidedResponse {
+    pub provider: String,
+    pub units: Uint128,
+}
+
+#[cw_serde]
+pub struct ReconciliationResponse {
+    /// the vault's unit_asset balance
+    pub balance: Uint128,
+    /// the units the vault should be holding
+    pub accounted_units: Uint128,
+    /// units held beyond those accounted for
+    pub surplus: Uint128,
+    /// units accounted for but missing from the balance
+    pub deficit: Uint128,
+}
+
+#[cw_serde]
+pub struct ProviderShare {
+    pub provider: String,
+    pub units_provided: Uint128,
+    /// the provider's fraction of the pool shares, and so of the unsold
+    /// units and any further earnings
+    pub share: Decimal,
+    pub earnings_withdrawn: Uint128,
+    pub unsold_reclaimed: Uint128,
+}
+
+#[cw_serde]
+pub struct ProviderSharesResponse {
+    pub total_units_provided: Uint128,
+    pub shares: Vec<ProviderShare>,
+}
+
+#[cw_serde]
+pub struct FeeRecipient {
+    pub recipient: String,
+    pub accrued: Uint128,
+    pub claimed: Uint128,
+}
+
+#[cw_serde]
+pub struct FeeAccountingResponse {
+    pub fees: Vec<Fee>,
+    pub referral_bps: Uint64,
+    /// spend_asset taken in fees across every sale
+    pub total_accrued: Uint128,
+    pub total_claimed: Uint128,
+    pub recipients: Vec<FeeRecipient>,
+}
+
+#[cw_serde]
+pub struct PurchaseResponse {
+    pub id: u64,
+    pub purchase: Purchase,
+}
+
+#[cw_serde]
+pub struct PurchasesResponse {
+    pub purchases: Vec<PurchaseResponse>,
+}
+
+#[cw_serde]
+pub struct BuyerSummaryResponse {
+    pub buyer: String,
+    pub purchase_count: u64,
+    pub total_units: Uint128,
+    pub total_cost: Uint128,
+    /// the volume-weighted average price across every purchase
+    pub average_price: Decimal,
+}
+
+#[cw_serde]
+pub struct ClearingResponse {
+    pub total_committed: Uint128,
+    pub clearing: Option<Clearing>,
+}
+
+#[cw_serde]
+pub struct CommitmentResponse {
+    pub buyer: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct VestingStatusResponse {
+    pub buyer: String,
+    /// the units bought under vesting
+    pub total: Uint128,
+    /// the units released so far
+    pub vested: Uint128,
+    pub claimed: Uint128,
+    /// the units released and not yet claimed
+    pub claimable: Uint128,
+}
+
+#[cw_serde]
+pub struct EpochStatusResponse {
+    pub current_epoch: Uint64,
+    /// the units released in the current epoch
+    pub epoch_allocation: Uint128,
+    /// the units that can still be bought in the current epoch
+    pub remaining_allocation: Uint128,
+    /// unix time in seconds at which the next epoch starts
+    pub next_epoch_start: Uint64,
+}
+
+#[cw_serde]
+pub struct SalePhaseResponse {
+    /// the index of the active phase, None before the first phase opens or
+    /// when no phases are configured
+    pub phase_id: Option<Uint64>,
+    pub phase: Option<SalePhase>,
+    /// whether the address can buy at the current block time
+    pub eligible: bool,
+    /// the units left in the address's allocation when the phase is gated
+    pub remaining_allocation: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub owner: Addr,
+    /// the address ownership has been offered to
+    pub pending_owner: Option<Addr>,
+    pub operators: Vec<Addr>,
+    pub pausers: Vec<Addr>,
+    pub relayers: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct ParameterHistoryResponse {
+    pub changes: Vec<ParameterChange>,
+}
+
+#[cw_serde]
+pub struct CurrentValsetResponse {
+    /// None until a relayer submits the first valset
+    pub valset: Option<Valset>,
+}
+
+#[cw_serde]
+pub struct ValidatorShareResponse {
+    pub accrued: Uint128,
+    pub claimed: Uint128,
 }
@@ -0,0 +1,84 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdResult, Uint128, Uint256, Uint512};
+
+/// the scale of the per share index, fine enough that units with many more
+/// decimals than the spend asset still accrue earnings
+const INDEX_PRECISION: Uint256 =
+    Uint256::from_u128(1_000_000_000_000_000_000_000_000_000_000_000_000);
+
+/// Provider proceeds per pool share, accumulated as sales are made. Shares
+/// issued later are checkpointed at the index they were issued at, so they
+/// only earn from the sales made after them.
+#[cw_serde]
+#[derive(Default)]
+pub struct EarningsIndex {
+    /// the proceeds earned by each pool share, scaled by INDEX_PRECISION
+    pub per_share: Uint256,
+    /// the proceeds spread over the index so far
+    pub proceeds: Uint128,
+}
+
+impl EarningsIndex {
+    /// spreads the proceeds made since the last accrual over `total_shares`
+    pub fn accrue(&mut self, proceeds: Uint128, total_shares: Uint128) {
+        if total_shares.is_zero() {
+            return;
+        }
+        let new_proceeds = proceeds.saturating_sub(self.proceeds);
+        self.per_share += Uint256::from(new_proceeds).multiply_ratio(INDEX_PRECISION, total_shares);
+        self.proceeds = proceeds;
+    }
+
+    /// the proceeds `shares` have earned since the index began, rounded down,
+    /// or up when checkpointing newly issued shares
+    pub fn earnings_of(&self, shares: Uint128, round_up: bool) -> StdResult<Uint128> {
+        let scaled = Uint256::from(shares).full_mul(self.per_share);
+        let precision = Uint512::from(INDEX_PRECISION);
+        let mut earnings = scaled / precision;
+        if round_up && !(scaled % precision).is_zero() {
+            earnings += Uint512::from(1u8);
+        }
+        Ok(Uint128::try_from(earnings)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_earnings_index_checkpoints_late_shares() {
+        let mut index = EarningsIndex::default();
+        index.accrue(Uint128::new(1000), Uint128::new(1000));
+        assert_eq!(
+            index.earnings_of(Uint128::new(1000), false),
+            Ok(Uint128::new(1000))
+        );
+
+        // shares issued now are checkpointed past the earlier proceeds
+        let checkpoint = index.earnings_of(Uint128::new(3000), true).unwrap();
+        index.accrue(Uint128::new(1400), Uint128::new(4000));
+        assert_eq!(
+            index.earnings_of(Uint128::new(1000), false),
+            Ok(Uint128::new(1100))
+        );
+        assert_eq!(
+            index.earnings_of(Uint128::new(3000), false).unwrap() - checkpoint,
+            Uint128::new(300)
+        );
+    }
+
+    #[test]
+    fn test_earnings_index_never_overpays() {
+        let mut index = EarningsIndex::default();
+        index.accrue(Uint128::new(10), Uint128::new(3));
+        let checkpoint = index.earnings_of(Uint128::new(7), true).unwrap();
+        index.accrue(Uint128::new(21), Uint128::new(10));
+
+        let early = index.earnings_of(Uint128::new(3), false).unwrap();
+        let late = index.earnings_of(Uint128::new(7), false).unwrap() - checkpoint;
+        assert!(early + late <= Uint128::new(21));
+        assert_eq!(early, Uint128::new(13));
+        assert_eq!(late, Uint128::new(7));
+    }
+}
@@ -1,3 +1,156 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Empty, Order, StdResult, Storage};
 
+use crate::{
+    state::{MarketConfig, OWNER, ROLE_MEMBERS},
+    ContractError,
+};
 
+/// Administrative roles. The owner implicitly holds every other role
+#[cw_serde]
+pub enum Role {
+    /// grants and revokes roles and transfers ownership
+    Owner {},
+    /// manages the auction on the seller's behalf
+    Operator {},
+    /// halts and resumes the vault
+    Pauser {},
+    /// relays validator set updates
+    Relayer {},
+}
 
+impl Role {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::Owner {} => "owner",
+            Role::Operator {} => "operator",
+            Role::Pauser {} => "pauser",
+            Role::Relayer {} => "relayer",
+        }
+    }
+}
+
+pub fn has_role(storage: &dyn Storage, address: &Addr, role: &Role) -> StdResult<bool> {
+    if OWNER.may_load(storage)?.as_ref() == Some(address) {
+        return Ok(true);
+    }
+    match role {
+        Role::Owner {} => Ok(false),
+        role => Ok(ROLE_MEMBERS.has(storage, (role.key(), address))),
+    }
+}
+
+/// errors unless `address` holds `role`
+pub fn ensure_role(
+    storage: &dyn Storage,
+    address: &Addr,
+    role: &Role,
+) -> Result<(), ContractError> {
+    if !has_role(storage, address, role)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// errors unless `address` is the seller
+pub fn ensure_seller(market_config: &MarketConfig, address: &Addr) -> Result<(), ContractError> {
+    if market_config.seller_address.as_ref() != Some(address) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// errors unless `address` is the seller or holds `role`
+pub fn ensure_seller_or_role(
+    storage: &dyn Storage,
+    market_config: &MarketConfig,
+    address: &Addr,
+    role: &Role,
+) -> Result<(), ContractError> {
+    if ensure_seller(market_config, address).is_ok() {
+        return Ok(());
+    }
+    ensure_role(storage, address, role)
+}
+
+pub fn grant_role(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    role: &Role,
+) -> Result<(), ContractError> {
+    if *role == (Role::Owner {}) {
+        return Err(ContractError::CannotGrantOwnerRole {});
+    }
+    ROLE_MEMBERS.save(storage, (role.key(), address), &Empty {})?;
+    Ok(())
+}
+
+pub fn revoke_role(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    role: &Role,
+) -> Result<(), ContractError> {
+    if *role == (Role::Owner {}) {
+        return Err(ContractError::CannotRevokeOwnerRole {});
+    }
+    ROLE_MEMBERS.remove(storage, (role.key(), address));
+    Ok(())
+}
+
+/// the addresses explicitly granted `role`
+pub fn role_members(storage: &dyn Storage, role: &Role) -> StdResult<Vec<Addr>> {
+    ROLE_MEMBERS
+        .prefix(role.key())
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+
+    #[test]
+    fn test_owner_holds_every_role() {
+        let mut storage = MockStorage::new();
+        let owner = Addr::unchecked("owner");
+        let operator = Addr::unchecked("operator");
+        OWNER.save(&mut storage, &owner).unwrap();
+        grant_role(&mut storage, &operator, &Role::Operator {}).unwrap();
+
+        for role in [Role::Owner {}, Role::Operator {}, Role::Pauser {}] {
+            assert_eq!(has_role(&storage, &owner, &role), Ok(true));
+        }
+        assert_eq!(has_role(&storage, &operator, &Role::Operator {}), Ok(true));
+        assert_eq!(has_role(&storage, &operator, &Role::Pauser {}), Ok(false));
+        assert_eq!(
+            ensure_role(&storage, &operator, &Role::Owner {}),
+            Err(ContractError::Unauthorized {})
+        );
+    }
+
+    #[test]
+    fn test_grant_and_revoke_role() {
+        let mut storage = MockStorage::new();
+        let pauser = Addr::unchecked("pauser");
+
+        assert_eq!(
+            grant_role(&mut storage, &pauser, &Role::Owner {}),
+            Err(ContractError::CannotGrantOwnerRole {})
+        );
+        grant_role(&mut storage, &pauser, &Role::Pauser {}).unwrap();
+        assert_eq!(
+            role_members(&storage, &Role::Pauser {}),
+            Ok(vec![pauser.clone()])
+        );
+        assert_eq!(role_members(&storage, &Role::Operator {}), Ok(vec![]));
+
+        assert_eq!(
+            revoke_role(&mut storage, &pauser, &Role::Owner {}),
+            Err(ContractError::CannotRevokeOwnerRole {})
+        );
+        revoke_role(&mut storage, &pauser, &Role::Pauser {}).unwrap();
+        assert_eq!(has_role(&storage, &pauser, &Role::Pauser {}), Ok(false));
+    }
+}
@@ -1,11 +1,19 @@
 use crate::{
-    fungible::{CheckedFungibleAsset},
-    market::Market,
+    allowlist::{validate_sale_phases, SalePhase},
+    contract::MIN_TOTAL_UNITS,
+    epoch::EpochRelease,
+    fee::{validate_fees, Fee, BPS_DENOMINATOR},
+    fungible::CheckedFungibleAsset,
+    governance::ParameterChange,
+    market::{Clearing, Market, PriceCurve},
+    pool::EarningsIndex,
+    valset::Valset,
+    vesting::{Vesting, VestingSchedule},
     ContractError,
 };
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Decimal, Uint128, Uint64};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Decimal, Empty, Uint128, Uint64};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[cw_serde]
 pub struct CheckedNft {
@@ -22,16 +30,159 @@ pub struct MarketConfig {
     pub start_price: Decimal,
     pub min_price: Decimal,
     pub target_duration: Uint128,
+    #[serde(default)]
+    pub price_curve: PriceCurve,
+    /// unix time in seconds at which the auction closes regardless of how
+    /// many units remain. when unset the auction closes after target_duration
+    pub end_time: Option<Uint64>,
+    /// cuts of every sale paid to fee recipients before the providers
+    #[serde(default)]
+    pub fees: Vec<Fee>,
+    /// basis points of a sale earned by the referrer named in the purchase
+    #[serde(default)]
+    pub referral_bps: Uint64,
+    /// basis points of every earnings withdrawal distributed across the
+    /// relayed validator set, none when zero
+    #[serde(default)]
+    pub validator_share_bps: Uint64,
+    #[serde(default)]
+    pub settlement: Settlement,
+    /// locks purchased units under a schedule instead of sending them right away
+    pub vesting: Option<VestingSchedule>,
+    /// caps the units sold in each epoch to a slice of the supply
+    pub epoch_release: Option<EpochRelease>,
+    /// the most units a single address can buy across every purchase
+    pub max_units_per_buyer: Option<Uint128>,
+    /// the most units a single purchase can buy
+    pub max_units_per_tx: Option<Uint128>,
+    /// the fewest units a single purchase can buy
+    pub min_units_per_tx: Option<Uint128>,
+    /// the smallest supply, in raw units, the auction can be scheduled with
+    #[serde(default = "default_min_total_units")]
+    pub min_total_units: Uint128,
+    /// phases the sale moves through, each open until the next one starts.
+    /// when empty the sale is public for its whole duration
+    #[serde(default)]
+    pub sale_phases: Vec<SalePhase>,
+    /// the DAO whose executed proposals can schedule, pause, tune and
+    /// finalize the auction
+    pub dao: Option<Addr>,
 }
 
-// validate impl ensuring either seller address or nft is set but not both
+fn default_min_total_units() -> Uint128 {
+    MIN_TOTAL_UNITS
+}
+
+/// How purchases are priced
+#[cw_serde]
+pub enum Settlement {
+    /// every purchase pays the price at the moment it is made
+    Continuous {},
+    /// purchases are escrowed as commitments and every buyer pays the single
+    /// price at which the supply clears
+    UniformClearing {},
+}
+
+impl Default for Settlement {
+    fn default() -> Self {
+        Settlement::Continuous {}
+    }
+}
+
+/// a single purchase of units from the vault
+#[cw_serde]
+pub struct Purchase {
+    pub buyer: Addr,
+    pub units: Uint128,
+    pub cost: Uint128,
+    /// the price per unit the buyer paid, cost / units
+    pub price: Decimal,
+    /// unix time in seconds of the block the purchase was made in
+    pub time: Uint64,
+}
+
+/// running totals of every purchase a buyer has made
+#[cw_serde]
+#[derive(Default)]
+pub struct BuyerTotals {
+    pub purchase_count: u64,
+    pub units: Uint128,
+    pub cost: Uint128,
+}
+
+#[cw_serde]
+pub enum Phase {
+    /// the auction has not been scheduled yet
+    NotScheduled {},
+    /// the auction is scheduled but has not started
+    Scheduled {},
+    /// units are for sale
+    Open {},
+    /// the auction sold out or ran out of time
+    Closed {},
+}
+
+// validate impl ensuring either seller address or nft is set but not both,
+// and that the market parameters describe a sellable auction
 impl MarketConfig {
     pub fn validate(&self) -> Result<(), ContractError> {
         match (self.seller_address.is_some(), self.seller_nft.is_some()) {
             (true, true) => Err(ContractError::CannotSetBothSellerAddressAndNft {}),
             (false, false) => Err(ContractError::MustSetEitherSellerAddressOrNft {}),
             _ => Ok(()),
+        }?;
+        if self.spend_asset == self.unit_asset {
+            return Err(ContractError::SpendAssetIsUnitAsset {});
+        }
+        if self.start_price.is_zero() {
+            return Err(ContractError::StartPriceCannotBeZero {});
+        }
+        if self.min_price > self.start_price {
+            return Err(ContractError::MinPriceAboveStartPrice {});
         }
+        if self.target_duration.is_zero() {
+            return Err(ContractError::TargetDurationCannotBeZero {});
+        }
+        if self.min_total_units.is_zero() {
+            return Err(ContractError::MinTotalUnitsCannotBeZero {});
+        }
+        self.price_curve.validate()?;
+        if let Some(vesting) = &self.vesting {
+            vesting.validate()?;
+        }
+        if let Some(epoch_release) = &self.epoch_release {
+            epoch_release.validate()?;
+        }
+        self.validate_purchase_limits()?;
+        validate_sale_phases(&self.sale_phases)?;
+        // commitments are only priced and sized once the auction clears, so
+        // none of the per purchase limits can be enforced on them
+        if self.settlement == (Settlement::UniformClearing {})
+            && (self.max_units_per_buyer.is_some()
+                || self.max_units_per_tx.is_some()
+                || self.min_units_per_tx.is_some()
+                || self.epoch_release.is_some()
+                || !self.sale_phases.is_empty())
+        {
+            return Err(ContractError::LimitsUnsupportedWithUniformClearing {});
+        }
+        if self.validator_share_bps > Uint64::new(BPS_DENOMINATOR) {
+            return Err(ContractError::ValidatorShareBpsTooHigh {});
+        }
+        validate_fees(&self.fees, self.referral_bps)
+    }
+
+    fn validate_purchase_limits(&self) -> Result<(), ContractError> {
+        let caps = [self.max_units_per_buyer, self.max_units_per_tx];
+        if caps.iter().flatten().any(|cap| cap.is_zero()) {
+            return Err(ContractError::PurchaseLimitCannotBeZero {});
+        }
+        if let Some(min_units) = self.min_units_per_tx {
+            if caps.iter().flatten().any(|cap| *cap < min_units) {
+                return Err(ContractError::MinUnitsPerTxAboveMax {});
+            }
+        }
+        Ok(())
     }
 }
 
@@ -39,6 +190,93 @@ pub const MARKET_STATE: Item<Market> = Item::new("market");
 pub const MARKET_CONFIG: Item<MarketConfig> = Item::new("market_config");
 pub const UNIX_START: Item<Uint64> = Item::new("unix_start");
 pub const TOTAL_WITHDRAWN: Item<Uint128> = Item::new("total_withdrawn");
+/// set once every provider has reclaimed their share of the unsold units
+pub const FINALIZED: Item<bool> = Item::new("finalized");
+/// pool shares whose unsold units have been reclaimed
+pub const FINALIZED_SHARES: Item<Uint128> = Item::new("finalized_shares");
+/// while set, purchases, commitments and scheduling are halted
+pub const PAUSED: Item<bool> = Item::new("paused");
+/// the owner of the vault, who holds every role
+pub const OWNER: Item<Addr> = Item::new("owner");
+/// the address ownership has been offered to, until it accepts
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+/// addresses granted each role, keyed by role
+pub const ROLE_MEMBERS: Map<(&str, &Addr), Empty> = Map::new("role_members");
+/// units provided for sale, keyed by provider
+pub const UNITS_PROVIDED: Map<&Addr, Uint128> = Map::new("units_provided");
+pub const TOTAL_UNITS_PROVIDED: Item<Uint128> = Item::new("total_units_provided");
+/// each provider's claim on the unsold units and later proceeds. units
+/// provided once sales have begun are issued shares at the pool's current
+/// units per share, so they do not share in what was already sold
+pub const POOL_SHARES: Map<&Addr, Uint128> = Map::new("pool_shares");
+pub const TOTAL_POOL_SHARES: Item<Uint128> = Item::new("total_pool_shares");
+/// provider proceeds earned per pool share
+pub const EARNINGS_INDEX: Item<EarningsIndex> = Item::new("earnings_index");
+/// what each provider's shares had already earned when they were issued,
+/// which is not owed to them
+pub const EARNINGS_CHECKPOINTS: Map<&Addr, Uint128> = Map::new("earnings_checkpoints");
+/// spend_asset earnings each provider has withdrawn
+pub const EARNINGS_WITHDRAWN: Map<&Addr, Uint128> = Map::new("earnings_withdrawn");
+/// unsold units each provider has reclaimed after the auction closed
+pub const UNSOLD_RECLAIMED: Map<&Addr, Uint128> = Map::new("unsold_reclaimed");
+pub const TOTAL_UNSOLD_RECLAIMED: Item<Uint128> = Item::new("total_unsold_reclaimed");
+/// spend_asset fees accrued to each fee recipient and referrer
+pub const FEES_ACCRUED: Map<&Addr, Uint128> = Map::new("fees_accrued");
+/// spend_asset fees each recipient has claimed
+pub const FEES_CLAIMED: Map<&Addr, Uint128> = Map::new("fees_claimed");
+pub const TOTAL_FEES_ACCRUED: Item<Uint128> = Item::new("total_fees_accrued");
+pub const TOTAL_FEES_CLAIMED: Item<Uint128> = Item::new("total_fees_claimed");
+/// spend_asset escrowed by each buyer under uniform clearing
+pub const COMMITMENTS: Map<&Addr, Uint128> = Map::new("commitments");
+pub const TOTAL_COMMITTED: Item<Uint128> = Item::new("total_committed");
+/// the outcome of settling the commitments, set once the auction clears
+pub const CLEARING: Item<Clearing> = Item::new("clearing");
+/// cleared units already sent to their buyers
+pub const COMMITTED_UNITS_CLAIMED: Item<Uint128> = Item::new("committed_units_claimed");
+/// units each buyer has bought under vesting
+pub const VESTING: Map<&Addr, Vesting> = Map::new("vesting");
+/// units held for buyers under vesting and not yet claimed
+pub const TOTAL_VESTING_LOCKED: Item<Uint128> = Item::new("total_vesting_locked");
+/// units sold in each epoch of the release schedule
+pub const EPOCH_UNITS_SOLD: Map<u64, Uint128> = Map::new("epoch_units_sold");
+/// units each buyer has bought in each gated sale phase, keyed by phase index
+pub const PHASE_UNITS_BOUGHT: Map<(u64, &Addr), Uint128> = Map::new("phase_units_bought");
+/// the id assigned to the next purchase
+pub const NEXT_PURCHASE_ID: Item<u64> = Item::new("next_purchase_id");
+pub const BUYER_TOTALS: Map<&Addr, BuyerTotals> = Map::new("buyer_totals");
+/// every change made by the DAO, keyed by id
+pub const PARAMETER_CHANGES: Map<u64, ParameterChange> = Map::new("parameter_changes");
+/// the id assigned to the next governance change
+pub const NEXT_PARAMETER_CHANGE_ID: Item<u64> = Item::new("next_parameter_change_id");
+/// the validator set withdrawn earnings are distributed across
+pub const VALSET: Item<Valset> = Item::new("valset");
+/// spend_asset earnings distributed to each validator
+pub const VALIDATOR_SHARES_ACCRUED: Map<&Addr, Uint128> = Map::new("validator_shares_accrued");
+/// spend_asset earnings each validator has claimed
+pub const VALIDATOR_SHARES_CLAIMED: Map<&Addr, Uint128> = Map::new("validator_shares_claimed");
+
+pub struct PurchaseIndexes<'a> {
+    pub buyer: MultiIndex<'a, Addr, Purchase, u64>,
+}
+
+impl<'a> IndexList<Purchase> for PurchaseIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Purchase>> + '_> {
+        let v: Vec<&dyn Index<Purchase>> = vec![&self.buyer];
+        Box::new(v.into_iter())
+    }
+}
+
+/// every purchase by id, indexed by buyer
+pub fn purchases<'a>() -> IndexedMap<'a, u64, Purchase, PurchaseIndexes<'a>> {
+    let indexes = PurchaseIndexes {
+        buyer: MultiIndex::new(
+            |_pk, purchase| purchase.buyer.clone(),
+            "purchases",
+            "purchases__buyer",
+        ),
+    };
+    IndexedMap::new("purchases", indexes)
+}
 
 #[cfg(test)]
 mod tests {
@@ -61,6 +299,20 @@ mod tests {
             start_price,
             min_price,
             target_duration,
+            price_curve: PriceCurve::default(),
+            end_time: None,
+            fees: vec![],
+            referral_bps: Uint64::zero(),
+            validator_share_bps: Uint64::zero(),
+            settlement: Settlement::default(),
+            vesting: None,
+            epoch_release: None,
+            max_units_per_buyer: None,
+            max_units_per_tx: None,
+            min_units_per_tx: None,
+            min_total_units: MIN_TOTAL_UNITS,
+            sale_phases: vec![],
+            dao: None,
         }
     }
 
@@ -130,4 +382,105 @@ mod tests {
             Err(ContractError::MustSetEitherSellerAddressOrNft {})
         );
     }
+
+    #[test]
+    fn test_market_config_parameter_validation() {
+        let valid = create_market_config(
+            Some(Addr::unchecked("seller_address")),
+            None,
+            CheckedFungibleAsset::Native {
+                denom: "spend_asset".to_string(),
+            },
+            CheckedFungibleAsset::Native {
+                denom: "unit_asset".to_string(),
+            },
+            Decimal::one(),
+            Decimal::percent(10),
+            Uint128::from(100u64),
+        );
+        assert_eq!(valid.validate(), Ok(()));
+
+        let mut config = valid.clone();
+        config.unit_asset = config.spend_asset.clone();
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::SpendAssetIsUnitAsset {})
+        );
+
+        let mut config = valid.clone();
+        config.start_price = Decimal::zero();
+        config.min_price = Decimal::zero();
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::StartPriceCannotBeZero {})
+        );
+
+        let mut config = valid.clone();
+        config.min_price = Decimal::percent(110);
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::MinPriceAboveStartPrice {})
+        );
+
+        let mut config = valid.clone();
+        config.target_duration = Uint128::zero();
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::TargetDurationCannotBeZero {})
+        );
+
+        let mut config = valid.clone();
+        config.min_total_units = Uint128::zero();
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::MinTotalUnitsCannotBeZero {})
+        );
+
+        let mut config = valid.clone();
+        config.validator_share_bps = Uint64::new(10_001);
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::ValidatorShareBpsTooHigh {})
+        );
+
+        let mut config = valid.clone();
+        config.max_units_per_tx = Some(Uint128::zero());
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::PurchaseLimitCannotBeZero {})
+        );
+
+        let mut config = valid.clone();
+        config.min_units_per_tx = Some(Uint128::new(10));
+        config.max_units_per_buyer = Some(Uint128::new(5));
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::MinUnitsPerTxAboveMax {})
+        );
+
+        let mut config = valid.clone();
+        config.min_units_per_tx = Some(Uint128::new(10));
+        config.max_units_per_tx = Some(Uint128::new(10));
+        config.max_units_per_buyer = Some(Uint128::new(100));
+        assert_eq!(config.validate(), Ok(()));
+
+        config.settlement = Settlement::UniformClearing {};
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::LimitsUnsupportedWithUniformClearing {})
+        );
+
+        let mut config = valid;
+        config.settlement = Settlement::UniformClearing {};
+        assert_eq!(config.validate(), Ok(()));
+        config.epoch_release = Some(EpochRelease {
+            epoch_duration: Uint64::new(10),
+            epochs: Uint64::new(5),
+            rollover: false,
+        });
+        assert_eq!(
+            config.validate(),
+            Err(ContractError::LimitsUnsupportedWithUniformClearing {})
+        );
+    }
 }
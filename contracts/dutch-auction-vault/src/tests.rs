@@ -1,21 +1,36 @@
 use std::ops::Sub;
 use std::str::FromStr;
 
+use crate::allowlist::{hash_pair, leaf_hash, AllowlistProof, SalePhase};
 use crate::contract::*;
+use crate::epoch::EpochRelease;
 use crate::fungible::CheckedFungibleAsset;
 use crate::fungible::FungibleAsset;
+use crate::governance::GovernanceChange;
 use crate::market::Cost;
 use crate::market::Market;
+use crate::market::PriceCurve;
+use crate::migrations::{MarketConfigV0_1, MARKET_CONFIG_V0_1};
 use crate::msg::*;
+use crate::roles::Role;
 use crate::state::CheckedNft;
 use crate::state::MarketConfig;
+use crate::state::Phase;
+use crate::state::Purchase;
+use crate::state::Settlement;
 use crate::state::MARKET_CONFIG;
 use crate::state::MARKET_STATE;
+use crate::valset::ValidatorPower;
+use crate::vesting::VestingSchedule;
 use crate::ContractError;
 
+use contract_upgrade::UpgradeError;
 use cosmwasm_std::to_binary;
 use cosmwasm_std::Coin;
 use cosmwasm_std::Decimal;
+use cosmwasm_std::Event;
+use cosmwasm_std::Timestamp;
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
 
 use cosmwasm_std::Uint128;
 use cosmwasm_std::Uint64;
@@ -49,7 +64,7 @@ pub fn cw20_base_contract() -> Box<dyn Contract<Empty>> {
 }
 
 pub fn dutch_auction_vault_contract() -> Box<dyn Contract<Empty>> {
-    let contract = ContractWrapper::new(execute, instantiate, query);
+    let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
     Box::new(contract)
 }
 
@@ -129,6 +144,21 @@ pub fn default_dutch_auction_instantiate_msg(
             contract_addr: nft_addr.to_string(),
             token_id: nft_token_id.to_string(),
         }),
+        price_curve: None,
+        end_time: None,
+        fees: vec![],
+        referral_bps: None,
+        validator_share_bps: None,
+        settlement: None,
+        vesting: None,
+        epoch_release: None,
+        max_units_per_buyer: None,
+        max_units_per_tx: None,
+        min_units_per_tx: None,
+        min_total_units: None,
+        sale_phases: vec![],
+        owner: None,
+        dao: None,
     }
 }
 
@@ -248,6 +278,16 @@ impl TestEnv {
             .unwrap()
     }
 
+    pub fn market_state_response(&self) -> MarketStateResponse {
+        self.app
+            .wrap()
+            .query_wasm_smart(
+                self.dutch_auction_vault_addr.clone(),
+                &QueryMsg::GetMarketState {},
+            )
+            .unwrap()
+    }
+
     pub fn mint_native(&mut self, recipient: Addr, denom: &str, amount: Uint128) {
         self.app
             .sudo(SudoMsg::Bank(BankSudo::Mint {
@@ -301,23 +341,104 @@ impl TestEnv {
             .unwrap();
     }
 
+    /// mints units to the provider and provides them to the vault
+    pub fn provide_units(&mut self, provider: Addr, units: Uint128) {
+        let vault_addr = self.dutch_auction_vault_addr.clone();
+        self.provide_units_to(&vault_addr, provider, units);
+    }
+
+    pub fn provide_units_to(&mut self, vault_addr: &Addr, provider: Addr, units: Uint128) {
+        let unit_asset = self.market_config().unit_asset;
+        self.mint(unit_asset.clone(), provider.clone(), units);
+        let provide_msg = ReceiveMsg::ProvideUnits {};
+        match unit_asset {
+            CheckedFungibleAsset::Native { denom } => self
+                .app
+                .execute_contract(
+                    provider,
+                    vault_addr.clone(),
+                    &ExecuteMsg::Fund(provide_msg),
+                    &[Coin {
+                        denom,
+                        amount: units,
+                    }],
+                )
+                .unwrap(),
+            CheckedFungibleAsset::Cw20 { contract_addr } => self
+                .app
+                .execute_contract(
+                    provider,
+                    contract_addr,
+                    &cw20::Cw20ExecuteMsg::Send {
+                        contract: vault_addr.to_string(),
+                        amount: units,
+                        msg: to_binary(&provide_msg).unwrap(),
+                    },
+                    &[],
+                )
+                .unwrap(),
+        };
+    }
+
     pub fn schedule_auction(&mut self, start_time_unix: u64) {
+        self.try_schedule_auction(start_time_unix).unwrap();
+    }
+
+    pub fn try_schedule_auction(&mut self, start_time_unix: u64) -> anyhow::Result<AppResponse> {
+        let vault_addr = self.dutch_auction_vault_addr.clone();
+        self.try_schedule_auction_on(&vault_addr, start_time_unix)
+    }
+
+    pub fn try_schedule_auction_on(
+        &mut self,
+        vault_addr: &Addr,
+        start_time_unix: u64,
+    ) -> anyhow::Result<AppResponse> {
         let msg = ExecuteMsg::ScheduleAuction {
             start_time_unix: start_time_unix.into(),
         };
 
+        self.app.execute_contract(
+            self.nft_addr.clone(),
+            vault_addr.clone(),
+            &ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
+                sender: self.seller.to_string(),
+                token_id: self.nft_token_id.clone(),
+                msg: to_binary(&msg).unwrap(),
+            }),
+            &[],
+        )
+    }
+
+    /// schedules the auction by actually sending the seller nft to the vault
+    pub fn send_nft_schedule_auction(&mut self, start_time_unix: u64) {
+        let msg = cw721_base::msg::ExecuteMsg::<Empty, Empty>::SendNft {
+            contract: self.dutch_auction_vault_addr.to_string(),
+            token_id: self.nft_token_id.clone(),
+            msg: to_binary(&ReceiveNftMsg::ScheduleAuction {
+                start_time_unix: start_time_unix.into(),
+            })
+            .unwrap(),
+        };
+
         self.app
-            .execute_contract(
+            .execute_contract(self.seller.clone(), self.nft_addr.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    pub fn nft_owner(&self) -> String {
+        let resp: cw721::OwnerOfResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
                 self.nft_addr.clone(),
-                self.dutch_auction_vault_addr.clone(),
-                &ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
-                    sender: self.seller.to_string(),
+                &cw721::Cw721QueryMsg::OwnerOf {
                     token_id: self.nft_token_id.clone(),
-                    msg: to_binary(&msg).unwrap(),
-                }),
-                &[],
+                    include_expired: None,
+                },
             )
             .unwrap();
+        resp.owner
     }
 
     pub fn block(&mut self) {
@@ -369,8 +490,25 @@ fn test_instantiate() {
                 unit_asset: crate::fungible::CheckedFungibleAsset::Native { denom: denom_unit },
                 start_price: instantiate_msg.start_price,
                 min_price: instantiate_msg.min_price,
-                target_duration: instantiate_msg.target_duration
-            }
+                target_duration: instantiate_msg.target_duration,
+                price_curve: PriceCurve::default(),
+                end_time: None,
+                fees: vec![],
+                referral_bps: Uint64::zero(),
+                validator_share_bps: Uint64::zero(),
+                settlement: Settlement::default(),
+                vesting: None,
+                epoch_release: None,
+                max_units_per_buyer: None,
+                max_units_per_tx: None,
+                min_units_per_tx: None,
+                min_total_units: MIN_TOTAL_UNITS,
+                sale_phases: vec![],
+                dao: None,
+            },
+            phase: Phase::NotScheduled {},
+            finalized: false,
+            paused: false,
         }
     );
 }
@@ -389,11 +527,7 @@ fn test_execute_receive_cw20() {
     let time = env.app.block_info().time;
     let total_units = Uint128::from(100000u128);
 
-    env.mint(
-        env.market_config().unit_asset,
-        env.dutch_auction_vault_addr.clone(),
-        total_units,
-    );
+    env.provide_units(env.seller.clone(), total_units);
 
     env.mint(
         env.market_config().spend_asset,
@@ -427,6 +561,11 @@ fn test_execute_receive_cw20() {
         amount: Uint128::new(10),
         msg: to_binary(&ReceiveMsg::BuyUnits {
             units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
         contract: env.dutch_auction_vault_addr.to_string(),
@@ -480,6 +619,11 @@ fn test_execute_receive_cw20() {
         amount: Uint128::new(20),
         msg: to_binary(&ReceiveMsg::BuyUnits {
             units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
         contract: env.dutch_auction_vault_addr.to_string(),
@@ -505,6 +649,11 @@ fn test_execute_receive_cw20() {
         amount: Uint128::new(20000000),
         msg: to_binary(&ReceiveMsg::BuyUnits {
             units: total_units + Uint128::new(1),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
         contract: env.dutch_auction_vault_addr.to_string(),
@@ -517,11 +666,7 @@ fn test_execute_receive_funds() {
     let mut env = TestEnv::setup(true, false);
     let time = env.app.block_info().time;
     let total_units = Uint128::from(100000u128);
-    env.mint(
-        env.market_config().unit_asset,
-        env.dutch_auction_vault_addr.clone(),
-        total_units,
-    );
+    env.provide_units(env.seller.clone(), total_units);
     env.mint(
         env.market_config().spend_asset,
         env.buyer.clone(),
@@ -554,6 +699,11 @@ fn test_execute_receive_funds() {
             env.dutch_auction_vault_addr.clone(),
             &ExecuteMsg::Fund(ReceiveMsg::BuyUnits {
                 units: Uint128::new(10),
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof: None,
             }),
             &[Coin {
                 denom: env.denom_spend.clone(),
@@ -601,6 +751,11 @@ fn test_execute_receive_funds() {
         amount: Uint128::new(20),
         msg: to_binary(&ReceiveMsg::BuyUnits {
             units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
         contract: env.dutch_auction_vault_addr.to_string(),
@@ -626,6 +781,11 @@ fn test_execute_receive_funds() {
         amount: Uint128::new(20000000),
         msg: to_binary(&ReceiveMsg::BuyUnits {
             units: total_units + Uint128::new(1),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
         contract: env.dutch_auction_vault_addr.to_string(),
@@ -633,77 +793,3019 @@ fn test_execute_receive_funds() {
 }
 
 #[test]
-fn test_execute_fund() {
+fn test_query_quotes() {
     let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let total_units = Uint128::from(100000u128);
 
-    let fund_msg = ExecuteMsg::Fund(ReceiveMsg::ProvideUnits {});
-    env.mint_native(
+    env.provide_units(env.seller.clone(), total_units);
+    env.mint(
+        env.market_config().spend_asset,
         env.buyer.clone(),
-        &env.denom_unit.clone(),
-        Uint128::new(100),
+        Uint128::new(20),
+    );
+
+    // nothing to quote before the auction is scheduled
+    env.app
+        .wrap()
+        .query_wasm_smart::<QuoteResponse>(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::QuoteBuy {
+                units: Uint128::new(10),
+            },
+        )
+        .unwrap_err();
+
+    env.schedule_auction(time.seconds() + 1);
+
+    // nor before it starts
+    env.app
+        .wrap()
+        .query_wasm_smart::<CurrentPriceResponse>(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::CurrentPrice {},
+        )
+        .unwrap_err();
+
+    env.block();
+    env.block();
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+
+    let quote: QuoteResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::QuoteBuy {
+                units: Uint128::new(10),
+            },
+        )
+        .unwrap();
+    let market_state = env.market_state();
+    let Cost(cost) = market_state
+        .calculate_cost(Uint128::new(10), time_elapsed)
+        .unwrap();
+    assert_eq!(quote.units, Uint128::new(10));
+    assert_eq!(quote.cost, cost);
+    assert_eq!(
+        quote.price,
+        market_state
+            .calculate_price(Uint128::new(10), time_elapsed)
+            .unwrap()
+    );
+
+    let spend_quote: QuoteResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::QuoteSpend { amount: quote.cost },
+        )
+        .unwrap();
+    assert!(spend_quote.cost <= quote.cost);
+    assert_eq!(
+        spend_quote.units,
+        market_state
+            .calculate_received(quote.cost, time_elapsed)
+            .unwrap()
     );
+
+    // buying at the quoted block costs exactly the quote
+    let cw20_send_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: quote.cost,
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
     env.app
         .execute_contract(
             env.buyer.clone(),
+            env.cw20_a_addr.clone(),
+            &cw20_send_msg,
+            &[],
+        )
+        .unwrap();
+
+    let buyer_balance = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
+        .unwrap();
+    assert_eq!(buyer_balance, Uint128::new(20) - quote.cost);
+
+    let current_price: CurrentPriceResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
             env.dutch_auction_vault_addr.clone(),
-            &fund_msg,
-            &[Coin {
-                denom: env.denom_unit.clone(),
-                amount: Uint128::new(30),
-            }],
+            &QueryMsg::CurrentPrice {},
         )
         .unwrap();
+    assert_eq!(current_price.time_elapsed, time_elapsed);
+    assert_eq!(current_price.price, quote.post_trade_price);
+}
 
-    let buyer_coin_balance: Uint128 = env
+#[test]
+fn test_buy_units_slippage_bounds() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(20),
+    );
+
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+    let now = env.app.block_info().time.seconds();
+    let time_elapsed = Uint128::from(now - (time.seconds() + 1));
+    let market_state = env.market_state();
+    let price = market_state
+        .calculate_price(Uint128::new(10), time_elapsed)
+        .unwrap();
+    let Cost(cost) = market_state
+        .calculate_cost(Uint128::new(10), time_elapsed)
+        .unwrap();
+
+    let send_buy = |env: &mut TestEnv, recv_msg: &ReceiveMsg| {
+        let cw20_send_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(10),
+            msg: to_binary(recv_msg).unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app.execute_contract(
+            env.buyer.clone(),
+            env.cw20_a_addr.clone(),
+            &cw20_send_msg,
+            &[],
+        )
+    };
+
+    let err: ContractError = send_buy(
+        &mut env,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::zero(),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        },
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(err, ContractError::ZeroTokens {});
+
+    let err: ContractError = send_buy(
+        &mut env,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: Some(price - Decimal::percent(1)),
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        },
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxPriceExceeded {
+            price,
+            max_price: price - Decimal::percent(1)
+        }
+    );
+
+    let err: ContractError = send_buy(
+        &mut env,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: Some(cost - Uint128::one()),
+            deadline: None,
+            referrer: None,
+            proof: None,
+        },
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxCostExceeded {
+            cost,
+            max_cost: cost - Uint128::one()
+        }
+    );
+
+    let err: ContractError = send_buy(
+        &mut env,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: Some(Uint64::new(now - 1)),
+            referrer: None,
+            proof: None,
+        },
+    )
+    .unwrap_err()
+    .downcast()
+    .unwrap();
+    assert_eq!(err, ContractError::DeadlineExceeded {});
+
+    send_buy(
+        &mut env,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: Some(price),
+            max_cost: Some(cost),
+            deadline: Some(Uint64::new(now)),
+            referrer: None,
+            proof: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(env.market_state().units_sold, Uint128::new(10));
+}
+
+#[test]
+fn test_buy_with_amount() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(20),
+    );
+
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+    let market_state = env.market_state();
+    let expected_units = market_state
+        .calculate_received(Uint128::new(5), time_elapsed)
+        .unwrap();
+    let Cost(cost) = market_state
+        .calculate_cost(expected_units, time_elapsed)
+        .unwrap();
+
+    let send_buy = |env: &mut TestEnv, min_units: Uint128| {
+        let cw20_send_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(5),
+            msg: to_binary(&ReceiveMsg::BuyWithAmount {
+                min_units,
+                deadline: None,
+                referrer: None,
+                proof: None,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app.execute_contract(
+            env.buyer.clone(),
+            env.cw20_a_addr.clone(),
+            &cw20_send_msg,
+            &[],
+        )
+    };
+
+    let err: ContractError = send_buy(&mut env, expected_units + Uint128::one())
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::MinUnitsNotMet {
+            units: expected_units,
+            min_units: expected_units + Uint128::one()
+        }
+    );
+
+    send_buy(&mut env, expected_units).unwrap();
+
+    let buyer_units = env
         .market_config()
         .unit_asset
         .query_balance(&env.app.wrap(), &env.buyer)
         .unwrap();
-    let contract_coin_balance: Uint128 = env
+    let buyer_spend = env
         .market_config()
-        .unit_asset
-        .query_balance(&env.app.wrap(), &env.dutch_auction_vault_addr)
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
         .unwrap();
-
-    assert_eq!(Uint128::new(70), buyer_coin_balance);
-    assert_eq!(Uint128::new(30), contract_coin_balance);
+    assert_eq!(buyer_units, expected_units);
+    assert_eq!(buyer_spend, Uint128::new(20) - cost);
 }
 
 #[test]
-fn test_execute_receive_nft() {
+fn test_buy_with_amount_buys_out_supply() {
     let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let total_units = Uint128::from(1000u128);
 
-    let start_time = env.app.block_info().time.seconds() + 2000u64;
+    env.provide_units(env.seller.clone(), total_units);
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(1_000_000),
+    );
 
-    let cw721_receive_msg = ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
-        sender: env.seller.to_string(),
-        token_id: env.nft_token_id.clone(),
-        msg: to_binary(&ReceiveNftMsg::ScheduleAuction {
-            start_time_unix: Uint64::new(start_time),
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+    let Cost(cost) = env
+        .market_state()
+        .calculate_cost(total_units, time_elapsed)
+        .unwrap();
+
+    // sending more than the whole supply costs buys all of it
+    let cw20_send_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: cost * Uint128::new(2),
+        msg: to_binary(&ReceiveMsg::BuyWithAmount {
+            min_units: total_units,
+            deadline: None,
+            referrer: None,
+            proof: None,
         })
         .unwrap(),
-    });
-
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
     env.app
         .execute_contract(
-            env.nft_addr.clone(),
-            env.dutch_auction_vault_addr.clone(),
-            &cw721_receive_msg,
+            env.buyer.clone(),
+            env.cw20_a_addr.clone(),
+            &cw20_send_msg,
             &[],
         )
         .unwrap();
 
-    let market_state: MarketStateResponse = env
-        .app
-        .wrap()
-        .query_wasm_smart(
-            env.dutch_auction_vault_addr.clone(),
-            &QueryMsg::GetMarketState {},
-        )
+    assert_eq!(env.market_state().units_sold, total_units);
+    let buyer_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
+        .unwrap();
+    let buyer_spend = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
         .unwrap();
+    assert_eq!(buyer_units, total_units);
+    assert_eq!(buyer_spend, Uint128::new(1_000_000) - cost);
+}
 
-    assert_eq!(
+#[test]
+fn test_finalize_returns_unsold_units() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let total_units = Uint128::from(100000u128);
+
+    env.provide_units(env.seller.clone(), total_units);
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(20),
+    );
+
+    env.schedule_auction(time.seconds() + 1);
+    assert_eq!(env.market_state_response().phase, Phase::Scheduled {});
+    env.block();
+    env.block();
+    assert_eq!(env.market_state_response().phase, Phase::Open {});
+
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(10),
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+
+    // the seller cannot reclaim while units are still for sale
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionNotClosed {});
+
+    // run out the target duration
+    env.app.update_block(|block| {
+        block.height += 50;
+        block.time = block.time.plus_seconds(100);
+    });
+    assert_eq!(env.market_state_response().phase, Phase::Closed {});
+
+    let err: ContractError = env
+        .app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionClosed {});
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap();
+
+    let seller_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_units, total_units - Uint128::new(10));
+    assert!(env.market_state_response().finalized);
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AlreadyFinalized {});
+}
+
+#[test]
+fn test_cancel_auction_returns_units_and_nft() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let total_units = Uint128::from(100000u128);
+
+    env.provide_units(env.seller.clone(), total_units);
+
+    env.send_nft_schedule_auction(time.seconds() + 10);
+    assert_eq!(env.nft_owner(), env.dutch_auction_vault_addr.to_string());
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::CancelAuction {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let resp = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::CancelAuction {},
+            &[],
+        )
+        .unwrap();
+    assert!(resp.events.iter().any(|e| e.ty == "wasm-units_refunded"));
+    assert!(resp.events.iter().any(|e| e.ty == "wasm-nft_returned"));
+
+    assert_eq!(env.nft_owner(), env.seller.to_string());
+    let seller_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_units, total_units);
+
+    let state = env.market_state_response();
+    assert_eq!(state.market, None);
+    assert_eq!(state.unix_start_time, None);
+    assert_eq!(state.config.seller_address, None);
+    assert_eq!(state.phase, Phase::NotScheduled {});
+}
+
+#[test]
+fn test_cancel_auction_after_sale_fails() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(20),
+    );
+
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(10),
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::CancelAuction {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionHasSales {});
+}
+
+#[test]
+fn test_provide_units_tops_up_and_reconciles() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let provider = Addr::unchecked("provider");
+    let total_units = Uint128::from(100000u128);
+
+    env.provide_units(env.seller.clone(), total_units);
+    env.schedule_auction(time.seconds() + 1);
+
+    // only units provided through ProvideUnits are put up for sale
+    let seller_provided: UnitsProvidedResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::UnitsProvided {
+                provider: env.seller.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(seller_provided.units, total_units);
+
+    // a second provider tops up the live market
+    env.mint_native(provider.clone(), &env.denom_unit.clone(), Uint128::new(500));
+    env.app
+        .execute_contract(
+            provider.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Fund(ReceiveMsg::ProvideUnits {}),
+            &[Coin {
+                denom: env.denom_unit.clone(),
+                amount: Uint128::new(500),
+            }],
+        )
+        .unwrap();
+    assert_eq!(env.market_state().total_units, Uint128::new(100500));
+
+    let provider_provided: UnitsProvidedResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::UnitsProvided {
+                provider: provider.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(provider_provided.units, Uint128::new(500));
+
+    // stray units sent without ProvideUnits show up as a surplus
+    env.mint(
+        env.market_config().unit_asset,
+        env.dutch_auction_vault_addr.clone(),
+        Uint128::new(42),
+    );
+    let reconciliation: ReconciliationResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::Reconciliation {},
+        )
+        .unwrap();
+    assert_eq!(
+        reconciliation,
+        ReconciliationResponse {
+            balance: Uint128::new(100542),
+            accounted_units: Uint128::new(100500),
+            surplus: Uint128::new(42),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            provider.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Reconcile {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Reconcile {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(env.market_state().total_units, Uint128::new(100542));
+
+    let reconciliation: ReconciliationResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::Reconciliation {},
+        )
+        .unwrap();
+    assert!(reconciliation.surplus.is_zero());
+}
+
+#[test]
+fn test_pooled_providers_split_earnings_and_unsold_units() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let provider = Addr::unchecked("provider");
+    let units_each = Uint128::from(100000u128);
+
+    env.provide_units(env.seller.clone(), units_each);
+    env.mint_native(provider.clone(), &env.denom_unit.clone(), units_each);
+    env.app
+        .execute_contract(
+            provider.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Fund(ReceiveMsg::ProvideUnits {}),
+            &[Coin {
+                denom: env.denom_unit.clone(),
+                amount: units_each,
+            }],
+        )
+        .unwrap();
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(1000),
+    );
+
+    env.schedule_auction(time.seconds() + 1);
+    assert_eq!(env.market_state().total_units, units_each + units_each);
+    env.block();
+    env.block();
+
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+    let Cost(cost) = env
+        .market_state()
+        .calculate_cost(Uint128::new(1000), time_elapsed)
+        .unwrap();
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: cost,
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(1000),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+
+    // only providers share in the pool
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::WithdrawEarnings {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let half_cost = cost.multiply_ratio(1u128, 2u128);
+    for recipient in [env.seller.clone(), provider.clone()] {
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::WithdrawEarnings {},
+                &[],
+            )
+            .unwrap();
+        // a second withdrawal has nothing left to pay out
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::WithdrawEarnings {},
+                &[],
+            )
+            .unwrap();
+        let earnings = env
+            .market_config()
+            .spend_asset
+            .query_balance(&env.app.wrap(), &recipient)
+            .unwrap();
+        assert_eq!(earnings, half_cost);
+    }
+
+    // run out the target duration
+    env.app.update_block(|block| {
+        block.height += 50;
+        block.time = block.time.plus_seconds(100);
+    });
+
+    let unsold_each = Uint128::new(99500);
+    for recipient in [env.seller.clone(), provider.clone()] {
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::Finalize {},
+                &[],
+            )
+            .unwrap();
+        let units = env
+            .market_config()
+            .unit_asset
+            .query_balance(&env.app.wrap(), &recipient)
+            .unwrap();
+        assert_eq!(units, unsold_each);
+        // finalized only once the last provider has reclaimed their units
+        assert_eq!(env.market_state_response().finalized, recipient == provider);
+    }
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            provider.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AlreadyFinalized {});
+
+    let shares: ProviderSharesResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::ProviderShares {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(shares.total_units_provided, units_each + units_each);
+    assert_eq!(
+        shares.shares,
+        vec![
+            ProviderShare {
+                provider: provider.to_string(),
+                units_provided: units_each,
+                share: Decimal::percent(50),
+                earnings_withdrawn: half_cost,
+                unsold_reclaimed: unsold_each,
+            },
+            ProviderShare {
+                provider: env.seller.to_string(),
+                units_provided: units_each,
+                share: Decimal::percent(50),
+                earnings_withdrawn: half_cost,
+                unsold_reclaimed: unsold_each,
+            },
+        ]
+    );
+
+    let page: ProviderSharesResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::ProviderShares {
+                start_after: Some(provider.to_string()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(page.shares.len(), 1);
+    assert_eq!(page.shares[0].provider, env.seller.to_string());
+
+    // everything has been paid out
+    let reconciliation: ReconciliationResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::Reconciliation {},
+        )
+        .unwrap();
+    assert_eq!(reconciliation.balance, Uint128::zero());
+    assert_eq!(reconciliation.accounted_units, Uint128::zero());
+}
+
+#[test]
+fn test_late_provider_only_shares_later_sales() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let seller = env.seller.clone();
+    let provider = Addr::unchecked("provider");
+
+    env.provide_units(seller.clone(), Uint128::new(100000));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(100_000_000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy = |env: &mut TestEnv, units: Uint128| -> Uint128 {
+        let time_elapsed =
+            Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+        let Cost(cost) = env
+            .market_state()
+            .calculate_cost(units, time_elapsed)
+            .unwrap();
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: cost,
+            msg: to_binary(&ReceiveMsg::BuyUnits {
+                units,
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof: None,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+            .unwrap();
+        cost
+    };
+    let withdraw = |env: &mut TestEnv, recipient: &Addr| -> Uint128 {
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::WithdrawEarnings {},
+                &[],
+            )
+            .unwrap();
+        env.market_config()
+            .spend_asset
+            .query_balance(&env.app.wrap(), recipient)
+            .unwrap()
+    };
+
+    // the seller sells a fifth of their units alone and takes the proceeds
+    let first_cost = buy(&mut env, Uint128::new(20000));
+    assert_eq!(withdraw(&mut env, &seller), first_cost);
+
+    // the top up matches the 80000 units left, so it buys half the pool
+    env.provide_units(provider.clone(), Uint128::new(80000));
+    assert_eq!(env.market_state().total_units, Uint128::new(180000));
+    assert_eq!(withdraw(&mut env, &provider), Uint128::zero());
+
+    let second_cost = buy(&mut env, Uint128::new(1000));
+    let half_second_cost = second_cost.multiply_ratio(1u128, 2u128);
+    assert_eq!(withdraw(&mut env, &seller), first_cost + half_second_cost);
+    assert_eq!(withdraw(&mut env, &provider), half_second_cost);
+
+    let shares: ProviderSharesResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::ProviderShares {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(shares.total_units_provided, Uint128::new(180000));
+    assert!(shares
+        .shares
+        .iter()
+        .all(|share| share.share == Decimal::percent(50)));
+
+    // the 159000 units left unsold are split evenly too
+    env.app.update_block(|block| {
+        block.height += 50;
+        block.time = block.time.plus_seconds(100);
+    });
+    for recipient in [seller, provider] {
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::Finalize {},
+                &[],
+            )
+            .unwrap();
+        let units = env
+            .market_config()
+            .unit_asset
+            .query_balance(&env.app.wrap(), &recipient)
+            .unwrap();
+        assert_eq!(units, Uint128::new(79500));
+    }
+}
+
+#[test]
+fn test_fees_and_referrals() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let protocol = Addr::unchecked("protocol");
+    let referrer = Addr::unchecked("referrer");
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            fees: vec![FeeUnverified {
+                bps: Uint64::new(500),
+                address: protocol.to_string(),
+                label: "protocol".to_string(),
+            }],
+            referral_bps: Some(Uint64::new(100)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(1000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy_msg = |env: &TestEnv, amount: Uint128, referrer: &Addr| cw20::Cw20ExecuteMsg::Send {
+        amount,
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(1000),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: Some(referrer.to_string()),
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+
+    // buyers cannot take the referral cut for themselves
+    let self_referred = buy_msg(&env, Uint128::new(1000), &env.buyer);
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.cw20_a_addr.clone(),
+            &self_referred,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::SelfReferral {});
+
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+    let Cost(cost) = env
+        .market_state()
+        .calculate_cost(Uint128::new(1000), time_elapsed)
+        .unwrap();
+    let referred = buy_msg(&env, cost, &referrer);
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &referred, &[])
+        .unwrap();
+
+    let protocol_fee = cost.multiply_ratio(500u128, 10_000u128);
+    let referral_fee = cost.multiply_ratio(100u128, 10_000u128);
+
+    // the seller earns the proceeds net of fees
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::WithdrawEarnings {},
+            &[],
+        )
+        .unwrap();
+    let seller_spend = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_spend, cost - protocol_fee - referral_fee);
+
+    for (recipient, fee) in [(&protocol, protocol_fee), (&referrer, referral_fee)] {
+        env.app
+            .execute_contract(
+                recipient.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::ClaimFees {},
+                &[],
+            )
+            .unwrap();
+        let balance = env
+            .market_config()
+            .spend_asset
+            .query_balance(&env.app.wrap(), recipient)
+            .unwrap();
+        assert_eq!(balance, fee);
+    }
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            protocol.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ClaimFees {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoFeesToClaim {});
+
+    let accounting: FeeAccountingResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::FeeAccounting {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(accounting.referral_bps, Uint64::new(100));
+    assert_eq!(accounting.total_accrued, protocol_fee + referral_fee);
+    assert_eq!(accounting.total_claimed, protocol_fee + referral_fee);
+    assert_eq!(
+        accounting.recipients,
+        vec![
+            FeeRecipient {
+                recipient: protocol.to_string(),
+                accrued: protocol_fee,
+                claimed: protocol_fee,
+            },
+            FeeRecipient {
+                recipient: referrer.to_string(),
+                accrued: referral_fee,
+                claimed: referral_fee,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_purchase_ledger() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(1000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+
+    let mut expected = vec![];
+    for units in [Uint128::new(100), Uint128::new(300)] {
+        env.block();
+        env.block();
+        let now = env.app.block_info().time.seconds();
+        let time_elapsed = Uint128::from(now - (time.seconds() + 1));
+        let Cost(cost) = env
+            .market_state()
+            .calculate_cost(units, time_elapsed)
+            .unwrap();
+
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: cost,
+            msg: to_binary(&ReceiveMsg::BuyUnits {
+                units,
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof: None,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+            .unwrap();
+
+        expected.push(PurchaseResponse {
+            id: expected.len() as u64,
+            purchase: Purchase {
+                buyer: env.buyer.clone(),
+                units,
+                cost,
+                price: Decimal::from_ratio(cost, units),
+                time: Uint64::new(now),
+            },
+        });
+    }
+
+    let query_purchases = |env: &TestEnv, start_after: Option<u64>, limit: Option<u32>| {
+        let resp: PurchasesResponse = env
+            .app
+            .wrap()
+            .query_wasm_smart(
+                env.dutch_auction_vault_addr.clone(),
+                &QueryMsg::Purchases {
+                    buyer: env.buyer.to_string(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap();
+        resp.purchases
+    };
+    assert_eq!(query_purchases(&env, None, None), expected);
+    assert_eq!(query_purchases(&env, None, Some(1)), expected[..1]);
+    assert_eq!(query_purchases(&env, Some(0), None), expected[1..]);
+
+    let total_cost = expected[0].purchase.cost + expected[1].purchase.cost;
+    let summary: BuyerSummaryResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::BuyerSummary {
+                buyer: env.buyer.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        summary,
+        BuyerSummaryResponse {
+            buyer: env.buyer.to_string(),
+            purchase_count: 2,
+            total_units: Uint128::new(400),
+            total_cost,
+            average_price: Decimal::from_ratio(total_cost, 400u128),
+        }
+    );
+
+    // buyers without purchases have an empty ledger
+    let summary: BuyerSummaryResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::BuyerSummary {
+                buyer: env.seller.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(summary.purchase_count, 0);
+    assert_eq!(summary.average_price, Decimal::zero());
+}
+
+#[test]
+fn test_uniform_clearing_settles_at_close() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let buyer2 = Addr::unchecked("buyer2");
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            price_curve: Some(PriceCurve::LinearDecay {}),
+            settlement: Some(Settlement::UniformClearing {}),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(3000),
+    );
+    env.mint(
+        env.market_config().spend_asset,
+        buyer2.clone(),
+        Uint128::new(1000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let send = |env: &TestEnv, amount: u128, msg: &ReceiveMsg| cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(amount),
+        msg: to_binary(msg).unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+
+    // buying at the instantaneous price is not available under clearing
+    let buy_msg = send(
+        &env,
+        1000,
+        &ReceiveMsg::BuyUnits {
+            units: Uint128::new(10),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        },
+    );
+    let err: ContractError = env
+        .app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::WrongSettlement {});
+
+    let commit_msg = send(&env, 3000, &ReceiveMsg::Commit {});
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &commit_msg, &[])
+        .unwrap();
+    env.block();
+    let commit_msg = send(&env, 1000, &ReceiveMsg::Commit {});
+    env.app
+        .execute_contract(buyer2.clone(), env.cw20_a_addr.clone(), &commit_msg, &[])
+        .unwrap();
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Settle {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionNotClosed {});
+
+    // run out the target duration, the clock stops at the min price
+    env.app.update_block(|block| {
+        block.height += 50;
+        block.time = block.time.plus_seconds(100);
+    });
+    let expected = env
+        .market_state()
+        .clear_commitments(Uint128::new(4000), env.market_config().target_duration)
+        .unwrap();
+    assert_eq!(expected.price, env.market_config().min_price);
+
+    env.app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Settle {},
+            &[],
+        )
+        .unwrap();
+    let clearing: ClearingResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(env.dutch_auction_vault_addr.clone(), &QueryMsg::Clearing {})
+        .unwrap();
+    assert_eq!(clearing.total_committed, Uint128::new(4000));
+    assert_eq!(clearing.clearing, Some(expected.clone()));
+
+    // both buyers pay the same price, whenever they committed
+    for (buyer, committed) in [(env.buyer.clone(), 3000u128), (buyer2.clone(), 1000u128)] {
+        env.app
+            .execute_contract(
+                buyer.clone(),
+                env.dutch_auction_vault_addr.clone(),
+                &ExecuteMsg::ClaimCommitment {},
+                &[],
+            )
+            .unwrap();
+        let units = env
+            .market_config()
+            .unit_asset
+            .query_balance(&env.app.wrap(), &buyer)
+            .unwrap();
+        let refund = env
+            .market_config()
+            .spend_asset
+            .query_balance(&env.app.wrap(), &buyer)
+            .unwrap();
+        assert_eq!(
+            units,
+            expected.units.multiply_ratio(committed, expected.committed)
+        );
+        assert_eq!(
+            refund,
+            (expected.committed - expected.cost).multiply_ratio(committed, expected.committed)
+        );
+    }
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            buyer2.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ClaimCommitment {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoCommitment {});
+
+    // the seller earns the clearing cost and reclaims what did not sell
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::WithdrawEarnings {},
+            &[],
+        )
+        .unwrap();
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Finalize {},
+            &[],
+        )
+        .unwrap();
+    let seller_spend = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    let seller_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_spend, expected.cost);
+    assert_eq!(seller_units, Uint128::new(100000) - expected.units);
+}
+
+#[test]
+fn test_uniform_clearing_clears_early() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            price_curve: Some(PriceCurve::LinearDecay {}),
+            settlement: Some(Settlement::UniformClearing {}),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(200000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    // enough is committed to buy the whole supply at the current price
+    let time_elapsed = Uint128::from(env.app.block_info().time.seconds() - (time.seconds() + 1));
+    let expected = env
+        .market_state()
+        .clear_commitments(Uint128::new(200000), time_elapsed)
+        .unwrap();
+    assert_eq!(expected.units, Uint128::new(100000));
+
+    let commit_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(200000),
+        msg: to_binary(&ReceiveMsg::Commit {}).unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &commit_msg, &[])
+        .unwrap();
+    assert_eq!(env.market_state_response().phase, Phase::Closed {});
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::Settle {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AlreadySettled {});
+
+    env.app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ClaimCommitment {},
+            &[],
+        )
+        .unwrap();
+    let buyer_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
+        .unwrap();
+    let buyer_spend = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
+        .unwrap();
+    assert_eq!(buyer_units, Uint128::new(100000));
+    assert_eq!(buyer_spend, Uint128::new(200000) - expected.cost);
+}
+
+#[test]
+fn test_vesting_locks_purchased_units() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let schedule = VestingSchedule {
+        epoch_duration: Uint64::new(100),
+        cliff_epochs: Uint64::new(1),
+        release_epochs: Uint64::new(2),
+    };
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            vesting: Some(schedule.clone()),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(1000),
+    );
+    let start_time = time.seconds() + 1;
+    env.schedule_auction(start_time);
+    env.block();
+    env.block();
+
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(1000),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: env.dutch_auction_vault_addr.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+
+    let buyer_units = |env: &TestEnv| {
+        env.market_config()
+            .unit_asset
+            .query_balance(&env.app.wrap(), &env.buyer)
+            .unwrap()
+    };
+    let vesting_status = |env: &TestEnv| -> VestingStatusResponse {
+        env.app
+            .wrap()
+            .query_wasm_smart(
+                env.dutch_auction_vault_addr.clone(),
+                &QueryMsg::VestingStatus {
+                    buyer: env.buyer.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    let claim_vested = |env: &mut TestEnv| {
+        env.app.execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ClaimVested {},
+            &[],
+        )
+    };
+
+    // the units stay in the vault until they vest
+    assert_eq!(buyer_units(&env), Uint128::zero());
+    let status = vesting_status(&env);
+    assert_eq!(status.total, Uint128::new(1000));
+    assert_eq!(status.vested, Uint128::zero());
+
+    let err: ContractError = claim_vested(&mut env).unwrap_err().downcast().unwrap();
+    assert_eq!(err, ContractError::NothingVested {});
+
+    // part way through the release
+    env.app.update_block(|block| {
+        block.time = block.time.plus_seconds(200);
+    });
+    let expected = schedule.vested(
+        Uint64::new(start_time),
+        Uint128::new(1000),
+        Uint64::new(env.app.block_info().time.seconds()),
+    );
+    assert!(!expected.is_zero() && expected < Uint128::new(1000));
+    claim_vested(&mut env).unwrap();
+    assert_eq!(buyer_units(&env), expected);
+
+    // everything is released after the last epoch
+    env.app.update_block(|block| {
+        block.time = block.time.plus_seconds(300);
+    });
+    claim_vested(&mut env).unwrap();
+    assert_eq!(buyer_units(&env), Uint128::new(1000));
+    assert_eq!(
+        vesting_status(&env),
+        VestingStatusResponse {
+            buyer: env.buyer.to_string(),
+            total: Uint128::new(1000),
+            vested: Uint128::new(1000),
+            claimed: Uint128::new(1000),
+            claimable: Uint128::zero(),
+        }
+    );
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ClaimVested {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoVesting {});
+}
+
+#[test]
+fn test_epoch_release_caps_purchases() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            price_curve: Some(PriceCurve::LinearDecay {}),
+            epoch_release: Some(EpochRelease {
+                epoch_duration: Uint64::new(50),
+                epochs: Uint64::new(4),
+                rollover: false,
+            }),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(100000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy = |env: &mut TestEnv, units: u128| {
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(units),
+            msg: to_binary(&ReceiveMsg::BuyUnits {
+                units: Uint128::new(units),
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof: None,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+    };
+    let epoch_status = |env: &TestEnv| -> EpochStatusResponse {
+        env.app
+            .wrap()
+            .query_wasm_smart(
+                env.dutch_auction_vault_addr.clone(),
+                &QueryMsg::EpochStatus {},
+            )
+            .unwrap()
+    };
+
+    let status = epoch_status(&env);
+    assert_eq!(status.current_epoch, Uint64::zero());
+    assert_eq!(status.epoch_allocation, Uint128::new(25000));
+    assert_eq!(status.remaining_allocation, Uint128::new(25000));
+    // the first epoch lasts a full epoch from the start
+    assert_eq!(status.next_epoch_start.u64(), time.seconds() + 1 + 50);
+
+    let err: ContractError = buy(&mut env, 25001).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::EpochAllocationExceeded {
+            remaining: Uint128::new(25000)
+        }
+    );
+    buy(&mut env, 25000).unwrap();
+
+    let err: ContractError = buy(&mut env, 1).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::EpochAllocationExceeded {
+            remaining: Uint128::zero()
+        }
+    );
+    let status = epoch_status(&env);
+    assert_eq!(status.remaining_allocation, Uint128::zero());
+    assert!(status.next_epoch_start.u64() > env.app.block_info().time.seconds());
+
+    // the next slice opens with the next epoch
+    env.app.update_block(|block| {
+        block.time = Timestamp::from_seconds(status.next_epoch_start.u64());
+    });
+    let next_status = epoch_status(&env);
+    assert_eq!(
+        next_status.current_epoch,
+        status.current_epoch + Uint64::one()
+    );
+    assert_eq!(next_status.remaining_allocation, Uint128::new(25000));
+    buy(&mut env, 25000).unwrap();
+    assert_eq!(env.market_state().units_sold, Uint128::new(50000));
+}
+
+#[test]
+fn test_purchase_limits() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            max_units_per_buyer: Some(Uint128::new(500)),
+            max_units_per_tx: Some(Uint128::new(400)),
+            min_units_per_tx: Some(Uint128::new(10)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(10000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy = |env: &mut TestEnv, units: u128| {
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(units),
+            msg: to_binary(&ReceiveMsg::BuyUnits {
+                units: Uint128::new(units),
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof: None,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+    };
+
+    let err: ContractError = buy(&mut env, 9).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::BelowMinUnitsPerTx {
+            units: Uint128::new(9),
+            min_units_per_tx: Uint128::new(10)
+        }
+    );
+    let err: ContractError = buy(&mut env, 401).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxUnitsPerTxExceeded {
+            units: Uint128::new(401),
+            max_units_per_tx: Uint128::new(400)
+        }
+    );
+
+    // the buyer cap holds across transactions
+    buy(&mut env, 400).unwrap();
+    let err: ContractError = buy(&mut env, 101).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxUnitsPerBuyerExceeded {
+            remaining: Uint128::new(100)
+        }
+    );
+    buy(&mut env, 100).unwrap();
+    let err: ContractError = buy(&mut env, 10).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxUnitsPerBuyerExceeded {
+            remaining: Uint128::zero()
+        }
+    );
+}
+
+#[test]
+fn test_purchase_limits_native_spend() {
+    let mut env = TestEnv::setup(false, false);
+    let time = env.app.block_info().time;
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            max_units_per_buyer: Some(Uint128::new(500)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(10000),
+    );
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy = |env: &mut TestEnv, units: u128| {
+        let buy_msg = ExecuteMsg::Fund(ReceiveMsg::BuyUnits {
+            units: Uint128::new(units),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        });
+        env.app.execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &buy_msg,
+            &[Coin {
+                denom: env.denom_spend.clone(),
+                amount: Uint128::new(units),
+            }],
+        )
+    };
+
+    buy(&mut env, 400).unwrap();
+    let err: ContractError = buy(&mut env, 101).unwrap_err().downcast().unwrap();
+    assert_eq!(
+        err,
+        ContractError::MaxUnitsPerBuyerExceeded {
+            remaining: Uint128::new(100)
+        }
+    );
+    buy(&mut env, 100).unwrap();
+}
+
+#[test]
+fn test_allowlist_sale_phases() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let start_time = time.seconds() + 1;
+
+    let buyer_leaf = leaf_hash(&env.buyer, Uint128::new(300));
+    let seller_leaf = leaf_hash(&env.seller, Uint128::new(200));
+    let merkle_root = hex::encode(hash_pair(buyer_leaf, seller_leaf));
+    let buyer_proof = AllowlistProof {
+        allocation: Uint128::new(300),
+        proof: vec![hex::encode(seller_leaf)],
+    };
+
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            sale_phases: vec![
+                SalePhase {
+                    name: "allowlist".to_string(),
+                    start_time: Uint64::new(start_time),
+                    merkle_root: Some(merkle_root),
+                },
+                SalePhase {
+                    name: "public".to_string(),
+                    start_time: Uint64::new(start_time + 50),
+                    merkle_root: None,
+                },
+            ],
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(10000),
+    );
+    env.mint(
+        env.market_config().spend_asset,
+        env.owner.clone(),
+        Uint128::new(10000),
+    );
+    env.schedule_auction(start_time);
+    env.block();
+    env.block();
+
+    let buy = |env: &mut TestEnv, buyer: &Addr, units: u128, proof: Option<AllowlistProof>| {
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(units),
+            msg: to_binary(&ReceiveMsg::BuyUnits {
+                units: Uint128::new(units),
+                max_price: None,
+                max_cost: None,
+                deadline: None,
+                referrer: None,
+                proof,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+    };
+    let sale_phase = |env: &TestEnv, address: &Addr, proof: Option<AllowlistProof>| {
+        env.app
+            .wrap()
+            .query_wasm_smart::<SalePhaseResponse>(
+                env.dutch_auction_vault_addr.clone(),
+                &QueryMsg::SalePhase {
+                    address: address.to_string(),
+                    proof,
+                },
+            )
+            .unwrap()
+    };
+
+    let status = sale_phase(&env, &env.buyer, Some(buyer_proof.clone()));
+    assert_eq!(status.phase_id, Some(Uint64::zero()));
+    assert!(status.eligible);
+    assert_eq!(status.remaining_allocation, Some(Uint128::new(300)));
+    assert!(!sale_phase(&env, &env.owner, None).eligible);
+
+    // the allowlist phase is closed to anyone without a proof
+    let owner = env.owner.clone();
+    let err: ContractError = buy(&mut env, &owner, 100, None)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NotAllowlisted {});
+
+    // the allocation is part of the leaf and cannot be inflated
+    let buyer = env.buyer.clone();
+    let inflated = AllowlistProof {
+        allocation: Uint128::new(1000),
+        ..buyer_proof.clone()
+    };
+    let err: ContractError = buy(&mut env, &buyer, 100, Some(inflated))
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NotAllowlisted {});
+
+    let err: ContractError = buy(&mut env, &buyer, 301, Some(buyer_proof.clone()))
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::AllowlistAllocationExceeded {
+            remaining: Uint128::new(300)
+        }
+    );
+    buy(&mut env, &buyer, 200, Some(buyer_proof.clone())).unwrap();
+    let status = sale_phase(&env, &env.buyer, Some(buyer_proof.clone()));
+    assert_eq!(status.remaining_allocation, Some(Uint128::new(100)));
+
+    // spending an amount is gated by the same proof
+    let buy_with_amount = |env: &mut TestEnv, proof: Option<AllowlistProof>| {
+        let buy_msg = cw20::Cw20ExecuteMsg::Send {
+            amount: Uint128::new(50),
+            msg: to_binary(&ReceiveMsg::BuyWithAmount {
+                min_units: Uint128::one(),
+                deadline: None,
+                referrer: None,
+                proof,
+            })
+            .unwrap(),
+            contract: env.dutch_auction_vault_addr.to_string(),
+        };
+        env.app
+            .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+    };
+    let err: ContractError = buy_with_amount(&mut env, None)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NotAllowlisted {});
+    buy_with_amount(&mut env, Some(buyer_proof.clone())).unwrap();
+    let status = sale_phase(&env, &env.buyer, Some(buyer_proof));
+    assert!(status.remaining_allocation.unwrap() < Uint128::new(100));
+
+    // the public phase is open to everyone
+    env.app.update_block(|block| {
+        block.time = Timestamp::from_seconds(start_time + 50);
+    });
+    let status = sale_phase(&env, &env.owner, None);
+    assert_eq!(status.phase_id, Some(Uint64::one()));
+    assert!(status.eligible);
+    assert_eq!(status.remaining_allocation, None);
+    buy(&mut env, &owner, 100, None).unwrap();
+}
+
+#[test]
+fn test_roles_and_ownership_transfer() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let operator = Addr::unchecked("operator");
+    let pauser = Addr::unchecked("pauser");
+    let vault = env.dutch_auction_vault_addr.clone();
+
+    let roles = |env: &TestEnv| -> RolesResponse {
+        env.app
+            .wrap()
+            .query_wasm_smart(env.dutch_auction_vault_addr.clone(), &QueryMsg::Roles {})
+            .unwrap()
+    };
+    let grant = |role: Role, address: &Addr| ExecuteMsg::GrantRole {
+        role,
+        address: address.to_string(),
+    };
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.buyer.clone(),
+            vault.clone(),
+            &grant(Role::Operator {}, &env.buyer),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &grant(Role::Owner {}, &operator),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CannotGrantOwnerRole {});
+
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &grant(Role::Operator {}, &operator),
+            &[],
+        )
+        .unwrap();
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &grant(Role::Pauser {}, &pauser),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        roles(&env),
+        RolesResponse {
+            owner: env.owner.clone(),
+            pending_owner: None,
+            operators: vec![operator.clone()],
+            pausers: vec![pauser.clone()],
+            relayers: vec![],
+        }
+    );
+
+    // an operator can cancel on the seller's behalf, the seller gets everything back
+    let total_units = Uint128::from(100000u128);
+    env.provide_units(env.seller.clone(), total_units);
+    env.send_nft_schedule_auction(time.seconds() + 10);
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            pauser.clone(),
+            vault.clone(),
+            &ExecuteMsg::CancelAuction {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    env.app
+        .execute_contract(
+            operator.clone(),
+            vault.clone(),
+            &ExecuteMsg::CancelAuction {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(env.nft_owner(), env.seller.to_string());
+    let seller_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_units, total_units);
+
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &ExecuteMsg::RevokeRole {
+                role: Role::Operator {},
+                address: operator.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert!(roles(&env).operators.is_empty());
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &ExecuteMsg::RevokeRole {
+                role: Role::Owner {},
+                address: env.owner.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CannotRevokeOwnerRole {});
+
+    // ownership only moves once the new owner accepts
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &ExecuteMsg::TransferOwnership {
+                new_owner: env.buyer.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(roles(&env).owner, env.owner);
+    assert_eq!(roles(&env).pending_owner, Some(env.buyer.clone()));
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            vault.clone(),
+            &ExecuteMsg::AcceptOwnership {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    env.app
+        .execute_contract(
+            env.buyer.clone(),
+            vault.clone(),
+            &ExecuteMsg::AcceptOwnership {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(roles(&env).owner, env.buyer);
+    assert_eq!(roles(&env).pending_owner, None);
+
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.owner.clone(),
+            vault,
+            &grant(Role::Operator {}, &operator),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn test_pause_halts_purchases_and_scheduling() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let pauser = Addr::unchecked("pauser");
+    let vault = env.dutch_auction_vault_addr.clone();
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(2000),
+    );
+
+    let err: ContractError = env
+        .app
+        .execute_contract(env.buyer.clone(), vault.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &ExecuteMsg::GrantRole {
+                role: Role::Pauser {},
+                address: pauser.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    env.app
+        .execute_contract(pauser.clone(), vault.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+    assert!(env.market_state_response().paused);
+    let err: ContractError = env
+        .try_schedule_auction(time.seconds() + 1)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused {});
+
+    env.app
+        .execute_contract(pauser.clone(), vault.clone(), &ExecuteMsg::Unpause {}, &[])
+        .unwrap();
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(1000),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: vault.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+
+    env.app
+        .execute_contract(pauser.clone(), vault.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+    let err: ContractError = env
+        .app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused {});
+
+    // withdrawals keep working while paused
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            vault.clone(),
+            &ExecuteMsg::WithdrawEarnings {},
+            &[],
+        )
+        .unwrap();
+
+    env.app
+        .execute_contract(pauser, vault, &ExecuteMsg::Unpause {}, &[])
+        .unwrap();
+    assert!(!env.market_state_response().paused);
+}
+
+#[test]
+fn test_valset_distributes_earnings() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let relayer = Addr::unchecked("relayer");
+    let val_a = Addr::unchecked("val_a");
+    let val_b = Addr::unchecked("val_b");
+    // the market opts into sending half of every withdrawal to the validators
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            validator_share_bps: Some(Uint64::new(5000)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    let vault = env.dutch_auction_vault_addr.clone();
+
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+    env.mint(
+        env.market_config().spend_asset,
+        env.buyer.clone(),
+        Uint128::new(2000),
+    );
+    env.app
+        .execute_contract(
+            env.owner.clone(),
+            vault.clone(),
+            &ExecuteMsg::GrantRole {
+                role: Role::Relayer {},
+                address: relayer.to_string(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    let update = |nonce: u128| {
+        ExecuteMsg::UpdateValset(ValsetUpdate {
+            nonce: Uint128::new(nonce),
+            power: Uint128::new(3),
+            valset: vec![
+                ValidatorPower {
+                    address: val_a.to_string(),
+                    power: Uint128::new(1),
+                },
+                ValidatorPower {
+                    address: val_b.to_string(),
+                    power: Uint128::new(2),
+                },
+            ],
+        })
+    };
+    let err: ContractError = env
+        .app
+        .execute_contract(env.buyer.clone(), vault.clone(), &update(1), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    env.app
+        .execute_contract(relayer.clone(), vault.clone(), &update(1), &[])
+        .unwrap();
+    let err: ContractError = env
+        .app
+        .execute_contract(relayer, vault.clone(), &update(1), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::StaleValsetNonce {
+            nonce: Uint128::new(1),
+            current: Uint128::new(1),
+        }
+    );
+    let resp: CurrentValsetResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(vault.clone(), &QueryMsg::CurrentValset {})
+        .unwrap();
+    let valset = resp.valset.unwrap();
+    assert_eq!(valset.nonce, Uint128::new(1));
+    assert_eq!(valset.validators.len(), 2);
+
+    env.schedule_auction(time.seconds() + 1);
+    env.block();
+    env.block();
+    let buy_msg = cw20::Cw20ExecuteMsg::Send {
+        amount: Uint128::new(1000),
+        msg: to_binary(&ReceiveMsg::BuyUnits {
+            units: Uint128::new(1000),
+            max_price: None,
+            max_cost: None,
+            deadline: None,
+            referrer: None,
+            proof: None,
+        })
+        .unwrap(),
+        contract: vault.to_string(),
+    };
+    env.app
+        .execute_contract(env.buyer.clone(), env.cw20_a_addr.clone(), &buy_msg, &[])
+        .unwrap();
+    let total_spent = env.market_state().total_spent;
+
+    // half of the seller's earnings are split between the validators by power
+    env.app
+        .execute_contract(
+            env.seller.clone(),
+            vault.clone(),
+            &ExecuteMsg::WithdrawEarnings {},
+            &[],
+        )
+        .unwrap();
+    let share = |env: &TestEnv, validator: &Addr| -> ValidatorShareResponse {
+        env.app
+            .wrap()
+            .query_wasm_smart(
+                vault.clone(),
+                &QueryMsg::ValidatorShare {
+                    validator: validator.to_string(),
+                },
+            )
+            .unwrap()
+    };
+    let share_a = share(&env, &val_a).accrued;
+    let share_b = share(&env, &val_b).accrued;
+    let distributed = total_spent.multiply_ratio(1u128, 2u128);
+    assert_eq!(share_a, distributed.multiply_ratio(1u128, 3u128));
+    assert_eq!(share_b, distributed.multiply_ratio(2u128, 3u128));
+    let seller_balance = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_balance + share_a + share_b, total_spent);
+
+    env.app
+        .execute_contract(
+            val_a.clone(),
+            vault.clone(),
+            &ExecuteMsg::ClaimValidatorShare {},
+            &[],
+        )
+        .unwrap();
+    let balance = env
+        .market_config()
+        .spend_asset
+        .query_balance(&env.app.wrap(), &val_a)
+        .unwrap();
+    assert_eq!(balance, share_a);
+    assert_eq!(share(&env, &val_a).claimed, share_a);
+    let err: ContractError = env
+        .app
+        .execute_contract(val_a, vault, &ExecuteMsg::ClaimValidatorShare {}, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoValidatorShareToClaim {});
+}
+
+#[test]
+fn test_dao_governs_auction() {
+    let mut env = TestEnv::setup(false, true);
+    let time = env.app.block_info().time;
+    let dao = Addr::unchecked("dao");
+    env.dutch_auction_vault_addr = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            seller_address: Some(env.seller.to_string()),
+            seller_nft: None,
+            dao: Some(dao.to_string()),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    let vault = env.dutch_auction_vault_addr.clone();
+    let total_units = Uint128::from(100000u128);
+    env.provide_units(env.seller.clone(), total_units);
+
+    let update = |min_price: Option<Decimal>, target_duration: Option<Uint128>| {
+        ExecuteMsg::UpdateParameters {
+            min_price,
+            target_duration,
+        }
+    };
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            vault.clone(),
+            &update(Some(Decimal::percent(20)), None),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    let res = env
+        .app
+        .execute_contract(
+            dao.clone(),
+            vault.clone(),
+            &update(Some(Decimal::percent(20)), None),
+            &[],
+        )
+        .unwrap();
+    assert!(res.has_event(
+        &Event::new("wasm-governance_change")
+            .add_attribute("change", "update_parameters")
+            .add_attribute("min_price", "0.2")
+    ));
+    assert_eq!(env.market_config().min_price, Decimal::percent(20));
+
+    // the DAO schedules the auction on the seller's behalf
+    env.app
+        .execute_contract(
+            dao.clone(),
+            vault.clone(),
+            &ExecuteMsg::ScheduleAuction {
+                start_time_unix: Uint64::new(time.seconds() + 10),
+            },
+            &[],
+        )
+        .unwrap();
+    env.app
+        .execute_contract(
+            dao.clone(),
+            vault.clone(),
+            &update(None, Some(Uint128::new(50))),
+            &[],
+        )
+        .unwrap();
+    assert_eq!(env.market_state().target_duration, Uint128::new(50));
+    assert_eq!(env.market_state().min_price, Decimal::percent(20));
+
+    env.app
+        .execute_contract(dao.clone(), vault.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+    assert!(env.market_state_response().paused);
+    env.app
+        .execute_contract(dao.clone(), vault.clone(), &ExecuteMsg::Unpause {}, &[])
+        .unwrap();
+
+    env.app
+        .update_block(|block| block.time = time.plus_seconds(10));
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            dao.clone(),
+            vault.clone(),
+            &update(Some(Decimal::percent(30)), None),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionAlreadyStarted {});
+
+    // once closed the DAO returns the seller's unsold units
+    env.app
+        .update_block(|block| block.time = time.plus_seconds(100));
+    env.app
+        .execute_contract(dao, vault.clone(), &ExecuteMsg::Finalize {}, &[])
+        .unwrap();
+    let seller_units = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.seller)
+        .unwrap();
+    assert_eq!(seller_units, total_units);
+
+    let history: ParameterHistoryResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            vault,
+            &QueryMsg::ParameterHistory {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        history
+            .changes
+            .iter()
+            .map(|change| change.change.key())
+            .collect::<Vec<&str>>(),
+        vec![
+            "update_parameters",
+            "schedule_auction",
+            "update_parameters",
+            "pause",
+            "unpause",
+            "finalize",
+        ]
+    );
+    assert_eq!(
+        history.changes[5].change,
+        GovernanceChange::Finalize {
+            unsold_units: total_units,
+        }
+    );
+}
+
+/// stores a market config the way 0.1.0 did
+fn legacy_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, "crates.io:dutch-auction-vault", "0.1.0")?;
+    MARKET_CONFIG_V0_1.save(
+        deps.storage,
+        &MarketConfigV0_1 {
+            seller_address: Some(info.sender),
+            seller_nft: None,
+            spend_asset: msg.spend_asset.into_checked(&deps.as_ref())?,
+            unit_asset: msg.unit_asset.into_checked(&deps.as_ref())?,
+            start_price: msg.start_price,
+            min_price: msg.min_price,
+            target_duration: msg.target_duration,
+        },
+    )?;
+    Ok(Response::default())
+}
+
+/// instantiates normally, then records a newer version than this build
+fn future_instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let response = instantiate(deps.branch(), env, info, msg)?;
+    cw2::set_contract_version(deps.storage, "crates.io:dutch-auction-vault", "99.0.0")?;
+    Ok(response)
+}
+
+#[test]
+fn test_migrate() {
+    let mut env = TestEnv::setup(false, false);
+    let code_id = env.app.store_code(dutch_auction_vault_contract());
+    let instantiate_with =
+        |app: &mut App, contract: Box<dyn Contract<Empty>>, msg: &InstantiateMsg| {
+            let id = app.store_code(contract);
+            app.instantiate_contract(
+                id,
+                Addr::unchecked("seller"),
+                msg,
+                &[],
+                "dutch_auction_vault".to_string(),
+                Some("seller".to_string()),
+            )
+            .unwrap()
+        };
+
+    let legacy_vault = instantiate_with(
+        &mut env.app,
+        Box::new(ContractWrapper::new(execute, legacy_instantiate, query)),
+        &env.instantiate_msg,
+    );
+    env.app
+        .migrate_contract(
+            env.seller.clone(),
+            legacy_vault.clone(),
+            &MigrateMsg { owner: None },
+            code_id,
+        )
+        .unwrap();
+
+    // the seller becomes the owner and the new settings take their defaults
+    let roles: RolesResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(legacy_vault.clone(), &QueryMsg::Roles {})
+        .unwrap();
+    assert_eq!(roles.owner, env.seller);
+    let state: MarketStateResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(legacy_vault, &QueryMsg::GetMarketState {})
+        .unwrap();
+    assert_eq!(state.config.seller_address, Some(env.seller.clone()));
+    assert_eq!(state.config.price_curve, PriceCurve::default());
+    assert_eq!(state.config.settlement, Settlement::default());
+    assert!(state.config.sale_phases.is_empty());
+    assert!(!state.paused);
+
+    let future_vault = instantiate_with(
+        &mut env.app,
+        Box::new(ContractWrapper::new(execute, future_instantiate, query)),
+        &env.instantiate_msg,
+    );
+    let err: ContractError = env
+        .app
+        .migrate_contract(
+            env.seller.clone(),
+            future_vault,
+            &MigrateMsg { owner: None },
+            code_id,
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Upgrade(UpgradeError::CannotMigrateToOlderVersion {
+            stored: "99.0.0".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_execute_fund() {
+    let mut env = TestEnv::setup(false, true);
+
+    let fund_msg = ExecuteMsg::Fund(ReceiveMsg::ProvideUnits {});
+    env.mint_native(
+        env.buyer.clone(),
+        &env.denom_unit.clone(),
+        Uint128::new(100),
+    );
+    env.app
+        .execute_contract(
+            env.buyer.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &fund_msg,
+            &[Coin {
+                denom: env.denom_unit.clone(),
+                amount: Uint128::new(30),
+            }],
+        )
+        .unwrap();
+
+    let buyer_coin_balance: Uint128 = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.buyer)
+        .unwrap();
+    let contract_coin_balance: Uint128 = env
+        .market_config()
+        .unit_asset
+        .query_balance(&env.app.wrap(), &env.dutch_auction_vault_addr)
+        .unwrap();
+
+    assert_eq!(Uint128::new(70), buyer_coin_balance);
+    assert_eq!(Uint128::new(30), contract_coin_balance);
+}
+
+#[test]
+fn test_execute_receive_nft() {
+    let mut env = TestEnv::setup(false, true);
+    env.provide_units(env.seller.clone(), Uint128::from(100000u128));
+
+    let start_time = env.app.block_info().time.seconds() + 2000u64;
+
+    let cw721_receive_msg = ExecuteMsg::ReceiveNft(cw721::Cw721ReceiveMsg {
+        sender: env.seller.to_string(),
+        token_id: env.nft_token_id.clone(),
+        msg: to_binary(&ReceiveNftMsg::ScheduleAuction {
+            start_time_unix: Uint64::new(start_time),
+        })
+        .unwrap(),
+    });
+
+    env.app
+        .execute_contract(
+            env.nft_addr.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &cw721_receive_msg,
+            &[],
+        )
+        .unwrap();
+
+    let market_state: MarketStateResponse = env
+        .app
+        .wrap()
+        .query_wasm_smart(
+            env.dutch_auction_vault_addr.clone(),
+            &QueryMsg::GetMarketState {},
+        )
+        .unwrap();
+
+    assert_eq!(
         market_state.unix_start_time.unwrap(),
         Uint64::new(start_time)
     );
 }
+
+/// ---------------------------------------------
+/// FAILURE CASES
+/// ---------------------------------------------
+
+#[test]
+fn test_instantiate_rejects_invalid_config() {
+    let mut env = TestEnv::setup(false, true);
+    let code_id = env.app.store_code(dutch_auction_vault_contract());
+    let valid = env.instantiate_msg.clone();
+
+    let cases: Vec<(InstantiateMsg, ContractError)> = vec![
+        (
+            InstantiateMsg {
+                seller_address: Some(env.seller.to_string()),
+                ..valid.clone()
+            },
+            ContractError::CannotSetBothSellerAddressAndNft {},
+        ),
+        (
+            InstantiateMsg {
+                seller_nft: None,
+                ..valid.clone()
+            },
+            ContractError::MustSetEitherSellerAddressOrNft {},
+        ),
+        (
+            InstantiateMsg {
+                unit_asset: valid.spend_asset.clone(),
+                ..valid.clone()
+            },
+            ContractError::SpendAssetIsUnitAsset {},
+        ),
+        (
+            InstantiateMsg {
+                start_price: Decimal::zero(),
+                min_price: Decimal::zero(),
+                ..valid.clone()
+            },
+            ContractError::StartPriceCannotBeZero {},
+        ),
+        (
+            InstantiateMsg {
+                min_price: Decimal::percent(200),
+                ..valid.clone()
+            },
+            ContractError::MinPriceAboveStartPrice {},
+        ),
+        (
+            InstantiateMsg {
+                target_duration: Uint128::zero(),
+                ..valid.clone()
+            },
+            ContractError::TargetDurationCannotBeZero {},
+        ),
+        (
+            InstantiateMsg {
+                price_curve: Some(PriceCurve::VolumeError { exponent: 0 }),
+                ..valid.clone()
+            },
+            ContractError::InvalidPriceCurve {},
+        ),
+        (
+            InstantiateMsg {
+                fees: vec![FeeUnverified {
+                    bps: Uint64::new(100),
+                    address: env.owner.to_string(),
+                    label: "Protocol".to_string(),
+                }],
+                ..valid.clone()
+            },
+            ContractError::FeeLabelNotLowerCase {},
+        ),
+        (
+            InstantiateMsg {
+                fees: vec![FeeUnverified {
+                    bps: Uint64::new(9000),
+                    address: env.owner.to_string(),
+                    label: "protocol".to_string(),
+                }],
+                referral_bps: Some(Uint64::new(2000)),
+                ..valid
+            },
+            ContractError::TotalFeeBpsTooHigh {},
+        ),
+    ];
+
+    for (msg, expected) in cases {
+        let err: ContractError = env
+            .app
+            .instantiate_contract(
+                code_id,
+                env.owner.clone(),
+                &msg,
+                &[],
+                "dutch_auction_vault".to_string(),
+                None,
+            )
+            .unwrap_err()
+            .downcast()
+            .unwrap();
+        assert_eq!(err, expected);
+    }
+}
+
+#[test]
+fn test_schedule_auction_rejects_invalid_parameters() {
+    let mut env = TestEnv::setup(false, true);
+    let now = env.app.block_info().time.seconds();
+
+    // no one can schedule through the address path before the nft owner is known
+    let err: ContractError = env
+        .app
+        .execute_contract(
+            env.seller.clone(),
+            env.dutch_auction_vault_addr.clone(),
+            &ExecuteMsg::ScheduleAuction {
+                start_time_unix: Uint64::new(now + 10),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err: ContractError = env
+        .try_schedule_auction(now + 10)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSupply {
+            total_units: Uint128::zero(),
+            min_total_units: MIN_TOTAL_UNITS,
+        }
+    );
+
+    env.provide_units(env.seller.clone(), MIN_TOTAL_UNITS - Uint128::one());
+    let err: ContractError = env
+        .try_schedule_auction(now + 10)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSupply {
+            total_units: MIN_TOTAL_UNITS - Uint128::one(),
+            min_total_units: MIN_TOTAL_UNITS,
+        }
+    );
+    env.provide_units(env.seller.clone(), Uint128::one());
+
+    let err: ContractError = env
+        .try_schedule_auction(now - 1)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionStartTimeInThePast {});
+
+    let err: ContractError = env
+        .try_schedule_auction(now + MAX_START_DELAY + 1)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionStartTimeTooFarInFuture {});
+
+    // nothing was written by the failed attempts
+    assert_eq!(env.market_state_response().market, None);
+
+    env.schedule_auction(now + 10);
+    let err: ContractError = env
+        .try_schedule_auction(now + 20)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionAlreadyScheduled {});
+    assert_eq!(
+        env.market_state_response().unix_start_time,
+        Some(Uint64::new(now + 10))
+    );
+    assert_eq!(env.market_state().total_units, MIN_TOTAL_UNITS);
+
+    // a hard end time must come after the start
+    let ending_vault = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            end_time: Some(Uint64::new(now + 5)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units_to(&ending_vault, env.seller.clone(), MIN_TOTAL_UNITS);
+    let err: ContractError = env
+        .try_schedule_auction_on(&ending_vault, now + 10)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionEndTimeBeforeStartTime {});
+
+    // the minimum supply follows the instantiate message, for assets with
+    // fewer decimals
+    let small_vault = instantiate_dutch_auction_vault(
+        &mut env.app,
+        env.owner.as_ref(),
+        &InstantiateMsg {
+            min_total_units: Some(Uint128::new(10)),
+            ..env.instantiate_msg.clone()
+        },
+    );
+    env.provide_units_to(&small_vault, env.seller.clone(), Uint128::new(9));
+    let err: ContractError = env
+        .try_schedule_auction_on(&small_vault, now + 10)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientSupply {
+            total_units: Uint128::new(9),
+            min_total_units: Uint128::new(10),
+        }
+    );
+    env.provide_units_to(&small_vault, env.seller.clone(), Uint128::one());
+    env.try_schedule_auction_on(&small_vault, now + 10).unwrap();
+}
@@ -0,0 +1,209 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Api, StdError, Uint128};
+
+use crate::{msg::ValsetUpdate, ContractError};
+
+/// the most validators a valset update can carry
+pub const MAX_VALIDATORS: usize = 100;
+
+/// a validator's voting power as relayed in a valset update
+#[cw_serde]
+pub struct ValidatorPower {
+    pub address: String,
+    pub power: Uint128,
+}
+
+#[cw_serde]
+pub struct Validator {
+    pub address: Addr,
+    pub power: Uint128,
+}
+
+/// The validator set withdrawn earnings are distributed across
+#[cw_serde]
+pub struct Valset {
+    pub nonce: Uint128,
+    /// the combined power of every validator
+    pub power: Uint128,
+    /// sorted by address
+    pub validators: Vec<Validator>,
+}
+
+impl ValsetUpdate {
+    /// validates the update, requiring its power to be the sum of the
+    /// validators' powers
+    pub fn into_checked(self, api: &dyn Api) -> Result<Valset, ContractError> {
+        if self.valset.is_empty() {
+            return Err(ContractError::EmptyValset {});
+        }
+        if self.valset.len() > MAX_VALIDATORS {
+            return Err(ContractError::TooManyValidators {});
+        }
+
+        let mut validators = self
+            .valset
+            .into_iter()
+            .map(|validator| {
+                if validator.power.is_zero() {
+                    return Err(ContractError::ValidatorPowerCannotBeZero {});
+                }
+                Ok(Validator {
+                    address: api.addr_validate(&validator.address)?,
+                    power: validator.power,
+                })
+            })
+            .collect::<Result<Vec<Validator>, ContractError>>()?;
+        validators.sort_by(|a, b| a.address.cmp(&b.address));
+        if let Some(pair) = validators
+            .windows(2)
+            .find(|pair| pair[0].address == pair[1].address)
+        {
+            return Err(ContractError::DuplicateValidator {
+                address: pair[0].address.to_string(),
+            });
+        }
+
+        let total = validators
+            .iter()
+            .try_fold(Uint128::zero(), |total, validator| {
+                total.checked_add(validator.power)
+            })
+            .map_err(StdError::from)?;
+        if total != self.power {
+            return Err(ContractError::ValsetPowerMismatch {
+                power: self.power,
+                total,
+            });
+        }
+
+        Ok(Valset {
+            nonce: self.nonce,
+            power: self.power,
+            validators,
+        })
+    }
+}
+
+impl Valset {
+    /// each validator's share of `amount` by power, along with the remainder
+    /// left over from rounding down
+    pub fn split(&self, amount: Uint128) -> (Vec<(Addr, Uint128)>, Uint128) {
+        let shares = self
+            .validators
+            .iter()
+            .map(|validator| {
+                (
+                    validator.address.clone(),
+                    amount.multiply_ratio(validator.power, self.power),
+                )
+            })
+            .collect::<Vec<(Addr, Uint128)>>();
+        let distributed = shares.iter().map(|(_, share)| *share).sum::<Uint128>();
+        (shares, amount - distributed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockApi;
+
+    use super::*;
+
+    fn update(nonce: u128, power: u128, valset: &[(&str, u128)]) -> ValsetUpdate {
+        ValsetUpdate {
+            nonce: Uint128::new(nonce),
+            power: Uint128::new(power),
+            valset: valset
+                .iter()
+                .map(|(address, power)| ValidatorPower {
+                    address: address.to_string(),
+                    power: Uint128::new(*power),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_valset_update_validation() {
+        let api = MockApi::default();
+        let valset = update(1, 6, &[("val_b", 4), ("val_a", 2)])
+            .into_checked(&api)
+            .unwrap();
+        assert_eq!(
+            valset.validators,
+            vec![
+                Validator {
+                    address: Addr::unchecked("val_a"),
+                    power: Uint128::new(2),
+                },
+                Validator {
+                    address: Addr::unchecked("val_b"),
+                    power: Uint128::new(4),
+                },
+            ]
+        );
+
+        assert_eq!(
+            update(1, 0, &[]).into_checked(&api),
+            Err(ContractError::EmptyValset {})
+        );
+        assert_eq!(
+            update(1, 2, &[("val_a", 2), ("val_b", 0)]).into_checked(&api),
+            Err(ContractError::ValidatorPowerCannotBeZero {})
+        );
+        assert_eq!(
+            update(1, 4, &[("val_a", 2), ("val_a", 2)]).into_checked(&api),
+            Err(ContractError::DuplicateValidator {
+                address: "val_a".to_string(),
+            })
+        );
+        assert_eq!(
+            update(1, 5, &[("val_a", 2), ("val_b", 4)]).into_checked(&api),
+            Err(ContractError::ValsetPowerMismatch {
+                power: Uint128::new(5),
+                total: Uint128::new(6),
+            })
+        );
+
+        let too_many = (0..=MAX_VALIDATORS)
+            .map(|i| (format!("val_{}", i), 1))
+            .collect::<Vec<(String, u128)>>();
+        let too_many = too_many
+            .iter()
+            .map(|(address, power)| (address.as_str(), *power))
+            .collect::<Vec<(&str, u128)>>();
+        assert_eq!(
+            update(1, too_many.len() as u128, &too_many).into_checked(&api),
+            Err(ContractError::TooManyValidators {})
+        );
+    }
+
+    #[test]
+    fn test_split_by_power() {
+        let api = MockApi::default();
+        let valset = update(1, 6, &[("val_a", 1), ("val_b", 2), ("val_c", 3)])
+            .into_checked(&api)
+            .unwrap();
+
+        let (shares, remainder) = valset.split(Uint128::new(600));
+        assert_eq!(
+            shares,
+            vec![
+                (Addr::unchecked("val_a"), Uint128::new(100)),
+                (Addr::unchecked("val_b"), Uint128::new(200)),
+                (Addr::unchecked("val_c"), Uint128::new(300)),
+            ]
+        );
+        assert_eq!(remainder, Uint128::zero());
+
+        let (shares, remainder) = valset.split(Uint128::new(10));
+        assert_eq!(
+            shares
+                .iter()
+                .map(|(_, share)| share.u128())
+                .collect::<Vec<u128>>(),
+            vec![1, 3, 5]
+        );
+        assert_eq!(remainder, Uint128::new(1));
+    }
+}
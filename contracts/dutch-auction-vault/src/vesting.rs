@@ -0,0 +1,124 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Uint128, Uint64};
+
+use crate::{epoch::Epoch, ContractError};
+
+/// Locks purchased units, releasing them in equal parts every epoch once the
+/// cliff has passed. Epochs are counted from the auction start, each lasting
+/// `epoch_duration` seconds.
+#[cw_serde]
+pub struct VestingSchedule {
+    /// the length of an epoch in seconds
+    pub epoch_duration: Uint64,
+    /// epochs during which nothing is released
+    pub cliff_epochs: Uint64,
+    /// epochs over which the units are released after the cliff
+    pub release_epochs: Uint64,
+}
+
+/// units a buyer has bought under vesting
+#[cw_serde]
+#[derive(Default)]
+pub struct Vesting {
+    pub total: Uint128,
+    pub claimed: Uint128,
+}
+
+impl VestingSchedule {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.epoch_duration.is_zero() {
+            return Err(ContractError::EpochDurationCannotBeZero {});
+        }
+        if self.release_epochs.is_zero() {
+            return Err(ContractError::ReleaseEpochsCannotBeZero {});
+        }
+        Ok(())
+    }
+
+    pub fn epoch(&self, start_time: Uint64) -> Epoch {
+        Epoch::new(self.epoch_duration, start_time)
+    }
+
+    /// the part of `total` released by `seconds`, for an auction started at `start_time`
+    pub fn vested(&self, start_time: Uint64, total: Uint128, seconds: Uint64) -> Uint128 {
+        if seconds < start_time {
+            return Uint128::zero();
+        }
+        let current_epoch = self.epoch(start_time).current_epoch(seconds);
+        let released_epochs = current_epoch
+            .saturating_sub(self.cliff_epochs)
+            .min(self.release_epochs);
+        total.multiply_ratio(released_epochs.u64(), self.release_epochs.u64())
+    }
+}
+
+impl Vesting {
+    pub fn locked(&self) -> Uint128 {
+        self.total - self.claimed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule() -> VestingSchedule {
+        VestingSchedule {
+            epoch_duration: Uint64::new(100),
+            cliff_epochs: Uint64::new(1),
+            release_epochs: Uint64::new(4),
+        }
+    }
+
+    #[test]
+    fn test_vesting_schedule_validation() {
+        assert!(schedule().validate().is_ok());
+        assert_eq!(
+            VestingSchedule {
+                epoch_duration: Uint64::zero(),
+                ..schedule()
+            }
+            .validate(),
+            Err(ContractError::EpochDurationCannotBeZero {})
+        );
+        assert_eq!(
+            VestingSchedule {
+                release_epochs: Uint64::zero(),
+                ..schedule()
+            }
+            .validate(),
+            Err(ContractError::ReleaseEpochsCannotBeZero {})
+        );
+    }
+
+    #[test]
+    fn test_vested_cliff_then_linear() {
+        let start = Uint64::new(1000);
+        let total = Uint128::new(1000);
+        let vested = |seconds: u64| schedule().vested(start, total, Uint64::new(seconds));
+
+        assert_eq!(vested(500), Uint128::zero());
+        // nothing is released during the cliff
+        assert_eq!(vested(1000), Uint128::zero());
+        assert_eq!(vested(1099), Uint128::zero());
+        assert_eq!(vested(1199), Uint128::zero());
+        assert_eq!(vested(1200), Uint128::new(250));
+        assert_eq!(vested(1350), Uint128::new(500));
+        assert_eq!(vested(1500), total);
+        assert_eq!(vested(9000), total);
+    }
+
+    #[test]
+    fn test_cliff_lasts_full_epoch_from_unaligned_start() {
+        // a start that is not a multiple of the epoch duration
+        let start = Uint64::new(1090);
+        let total = Uint128::new(1000);
+        let vested = |seconds: u64| schedule().vested(start, total, Uint64::new(seconds));
+
+        assert_eq!(vested(1100), Uint128::zero());
+        assert_eq!(vested(1289), Uint128::zero());
+        assert_eq!(vested(1290), Uint128::new(250));
+        assert_eq!(vested(1589), Uint128::new(750));
+        assert_eq!(vested(1590), total);
+    }
+}
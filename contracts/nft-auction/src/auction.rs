@@ -49,6 +49,15 @@ pub struct Auction {
     pub winning_bid: Option<Bid>,
     pub fees: Vec<Fee>,
     pub payout_address: Addr,
+    /// while paused no bids are accepted and the NFT cannot be received
+    #[serde(default)]
+    pub paused: bool,
+    /// set once the auction is cancelled or ended
+    #[serde(default)]
+    pub settled: bool,
+    /// a bid of at least this much wins the auction right away
+    #[serde(default)]
+    pub buy_now_price: Option<Uint128>,
 }
 
 impl Fee {
@@ -137,6 +146,9 @@ impl Auction {
             winning_bid: None,
             fees,
             payout_address,
+            paused: false,
+            settled: false,
+            buy_now_price: None,
         }
     }
 
@@ -163,6 +175,12 @@ impl Auction {
             return Err(ContractError::TotalFeeBpsTooHigh {});
         }
 
+        if let Some(buy_now_price) = self.buy_now_price {
+            if buy_now_price <= self.reserve_price {
+                return Err(ContractError::BuyNowPriceTooLow {});
+            }
+        }
+
         Ok(())
     }
 
@@ -172,6 +190,10 @@ impl Auction {
         owner: Addr,
         contract_addr: Addr,
     ) -> Result<(), ContractError> {
+        if self.paused {
+            return Err(ContractError::Paused {});
+        }
+
         if self.nft.received {
             return Err(ContractError::NftAlreadyReceived {});
         }
@@ -199,6 +221,14 @@ impl Auction {
         amount: Uint128,
         now: Timestamp,
     ) -> Result<(), ContractError> {
+        if self.paused {
+            return Err(ContractError::Paused {});
+        }
+
+        if self.is_settled() {
+            return Err(ContractError::AuctionSettled {});
+        }
+
         if now < self.start_time {
             return Err(ContractError::AuctionNotStarted {});
         }
@@ -229,6 +259,15 @@ impl Auction {
 
         let bid = Bid::new(bidder, amount, now);
 
+        // buying now ends and settles the auction with this bid
+        if self.buy_now_price.is_some_and(|price| amount >= price) {
+            self.end_time = now;
+            self.current_bid = None;
+            self.winning_bid = Some(bid);
+            self.settled = true;
+            return Ok(());
+        }
+
         if self.dynamic_bidding_extension.enabled {
             self.end_time =
                 now.plus_seconds(self.dynamic_bidding_extension.time_extension_secs.u64());
@@ -238,7 +277,38 @@ impl Auction {
         Ok(())
     }
 
+    /// whether the auction has been cancelled or ended, freeing the NFT to be
+    /// listed again. auctions ended before `settled` was stored have a winning bid
+    pub fn is_settled(&self) -> bool {
+        self.settled || self.winning_bid.is_some()
+    }
+
+    /// cancels the auction on behalf of the NFT owner, which is only allowed
+    /// before the first bid
+    pub fn cancel(&mut self, sender: &Addr) -> Result<(), ContractError> {
+        if self.is_settled() {
+            return Err(ContractError::AuctionSettled {});
+        }
+
+        if *sender != self.nft.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if self.current_bid.is_some() {
+            return Err(ContractError::AuctionHasBids {});
+        }
+
+        self.settled = true;
+        Ok(())
+    }
+
+    /// settles the auction once it has ended, returning the winning bid or
+    /// None if nobody bid
     pub fn end_auction(&mut self, now: Timestamp) -> Result<Option<Bid>, ContractError> {
+        if self.is_settled() {
+            return Err(ContractError::AuctionSettled {});
+        }
+
         if now < self.end_time {
             return Err(ContractError::AuctionNotEnded {});
         }
@@ -246,6 +316,7 @@ impl Auction {
         let winning_bid = self.current_bid.clone();
         self.winning_bid = winning_bid.clone();
         self.current_bid = None;
+        self.settled = true;
         Ok(winning_bid)
     }
 }
@@ -416,6 +487,31 @@ mod tests {
         assert!(auction.nft.received);
     }
 
+    #[test]
+    fn test_paused_auction_rejects_nft_and_bids() {
+        let mut auction = create_test_auction();
+        auction.paused = true;
+
+        let result = auction.receive_nft(
+            auction.nft.token_id.clone(),
+            auction.nft.owner.clone(),
+            auction.nft.contract_addr.clone(),
+        );
+        assert_eq!(result.unwrap_err(), ContractError::Paused {});
+
+        auction.paused = false;
+        receive_test_nft(&mut auction);
+        auction.paused = true;
+        let now = auction.start_time;
+        let result = auction.place_bid(Addr::unchecked("Bob"), 120_u128.into(), now);
+        assert_eq!(result.unwrap_err(), ContractError::Paused {});
+
+        auction.paused = false;
+        assert!(auction
+            .place_bid(Addr::unchecked("Bob"), 120_u128.into(), now)
+            .is_ok());
+    }
+
     #[test]
     fn test_place_bid_before_nft_received() {
         let mut auction = create_test_auction();
@@ -454,9 +550,94 @@ mod tests {
         let bid = winning_bid.unwrap();
         assert_eq!(bid.bidder, "Bob");
         assert_eq!(bid.amount, Uint128::from(120_u128));
+        assert!(auction.is_settled());
         assert_eq!(bid, auction.winning_bid.unwrap());
     }
 
+    #[test]
+    fn test_end_auction_without_bids() {
+        let mut auction = create_test_auction();
+        receive_test_nft(&mut auction);
+        let now = auction.end_time;
+
+        assert_eq!(auction.end_auction(now), Ok(None));
+        assert!(auction.is_settled());
+        assert_eq!(
+            auction.end_auction(now),
+            Err(ContractError::AuctionSettled {})
+        );
+    }
+
+    #[test]
+    fn test_cancel_auction() {
+        let mut auction = create_test_auction();
+        receive_test_nft(&mut auction);
+        let now = auction.start_time;
+
+        assert_eq!(
+            auction.cancel(&Addr::unchecked("Bob")),
+            Err(ContractError::Unauthorized {})
+        );
+
+        let mut with_bid = auction.clone();
+        with_bid
+            .place_bid(Addr::unchecked("Bob"), 120_u128.into(), now)
+            .unwrap();
+        assert_eq!(
+            with_bid.cancel(&Addr::unchecked("Alice")),
+            Err(ContractError::AuctionHasBids {})
+        );
+
+        auction.cancel(&Addr::unchecked("Alice")).unwrap();
+        assert!(auction.is_settled());
+        assert_eq!(
+            auction.place_bid(Addr::unchecked("Bob"), 120_u128.into(), now),
+            Err(ContractError::AuctionSettled {})
+        );
+        assert_eq!(
+            auction.end_auction(auction.end_time),
+            Err(ContractError::AuctionSettled {})
+        );
+    }
+
+    #[test]
+    fn test_place_bid_buy_now() {
+        let mut auction = create_test_auction();
+        auction.buy_now_price = Some(Uint128::new(500));
+        receive_test_nft(&mut auction);
+        let now = auction.start_time;
+
+        auction
+            .place_bid(Addr::unchecked("Bob"), 120_u128.into(), now)
+            .unwrap();
+        assert!(!auction.is_settled());
+
+        auction
+            .place_bid(Addr::unchecked("Charlie"), 500_u128.into(), now)
+            .unwrap();
+        assert!(auction.is_settled());
+        assert_eq!(auction.end_time, now);
+        assert_eq!(auction.current_bid, None);
+        assert_eq!(
+            auction.winning_bid,
+            Some(Bid::new(Addr::unchecked("Charlie"), 500_u128.into(), now))
+        );
+        assert_eq!(
+            auction.place_bid(Addr::unchecked("Bob"), 600_u128.into(), now),
+            Err(ContractError::AuctionSettled {})
+        );
+    }
+
+    #[test]
+    fn test_auction_validation_buy_now_price() {
+        let mut auction = create_test_auction();
+        auction.buy_now_price = Some(auction.reserve_price);
+        assert_eq!(auction.validate(), Err(ContractError::BuyNowPriceTooLow {}));
+
+        auction.buy_now_price = Some(auction.reserve_price + Uint128::one());
+        assert!(auction.validate().is_ok());
+    }
+
     #[test]
     fn test_fee_validation_success() {
         let fee = Fee::new(
@@ -1,27 +1,51 @@
+use contract_upgrade::check_upgrade;
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, entry_point, to_binary, wasm_execute, BankMsg, Binary, Deps, DepsMut, Env, Event,
-    MessageInfo, Response, StdError, StdResult, Timestamp, Uint128,
+    coins, from_binary, to_binary, wasm_execute, Addr, Api, BankMsg, Binary, Deps, DepsMut, Env,
+    Event, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128,
 };
 use cw2::set_contract_version;
 use cw721::Cw721ReceiveMsg;
+use cw_storage_plus::Bound;
 
 use crate::auction::{Auction, Bid, DynamicBiddingExtension, Fee, Nft};
 use crate::error::ContractError;
-use crate::msg::{AuctionInfoResponse, BidHistoryResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{AUCTION_INFO, BID_HISTORY};
+use crate::migrations::{migrate_from_v0_1, migrate_from_v0_2, v0_2, v0_3};
+use crate::msg::{
+    AuctionInfoResponse, AuctionsResponse, BidHistoryResponse, ExecuteMsg, FeeUnverified,
+    InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg,
+};
+use crate::state::{auctions, ADMIN, BIDS, NEXT_AUCTION_ID, PAUSED};
 
 const CONTRACT_NAME: &str = "crates.io:nft-auction";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let admin = msg
+        .admin
+        .map(|admin| deps.api.addr_validate(&admin))
+        .transpose()?
+        .unwrap_or(info.sender);
+    ADMIN.save(deps.storage, &admin)?;
+    NEXT_AUCTION_ID.save(deps.storage, &0)?;
+    PAUSED.save(deps.storage, &false)?;
+
+    let Some(msg) = msg.auction else {
+        return Ok(Response::default());
+    };
+
     let nft = Nft::new(
         msg.nft.token_id,
         deps.api.addr_validate(&msg.nft.owner)?,
@@ -34,31 +58,85 @@ pub fn instantiate(
         min_bid_increase: msg.dynamic_bidding_extension.min_bid_increase,
     };
 
-    let auction = Auction::new(
-        nft,
-        Timestamp::from_seconds(msg.start_time.into()),
-        Timestamp::from_seconds(msg.end_time.into()),
-        Uint128::from(msg.reserve_price),
-        dynamic_bidding_extension,
-        msg.denom,
-        msg.fees
-            .into_iter()
-            .map(|fee| {
-                Ok(Fee::new(
-                    fee.bps,
-                    deps.api.addr_validate(&fee.address)?,
-                    fee.label,
-                ))
-            })
-            .collect::<Result<Vec<Fee>, StdError>>()?,
-        deps.api.addr_validate(&msg.payout_address)?,
-    );
+    let auction = Auction {
+        buy_now_price: msg.buy_now_price.map(Uint128::from),
+        ..Auction::new(
+            nft,
+            Timestamp::from_seconds(msg.start_time.into()),
+            Timestamp::from_seconds(msg.end_time.into()),
+            Uint128::from(msg.reserve_price),
+            dynamic_bidding_extension,
+            msg.denom,
+            check_fees(deps.api, msg.fees)?,
+            deps.api.addr_validate(&msg.payout_address)?,
+        )
+    };
+
+    let auction_id = list_auction(deps.storage, &auction)?;
+
+    Ok(Response::default().add_attribute("auction_id", auction_id.to_string()))
+}
+
+fn check_fees(api: &dyn Api, fees: Vec<FeeUnverified>) -> Result<Vec<Fee>, StdError> {
+    fees.into_iter()
+        .map(|fee| {
+            Ok(Fee::new(
+                fee.bps,
+                api.addr_validate(&fee.address)?,
+                fee.label,
+            ))
+        })
+        .collect()
+}
 
+/// validates and saves a new auction, returning its id. an NFT can only be
+/// listed again once its previous auction is settled
+fn list_auction(storage: &mut dyn Storage, auction: &Auction) -> Result<u64, ContractError> {
+    ensure_not_paused(storage)?;
     auction.validate()?;
 
-    AUCTION_INFO.save(deps.storage, &auction)?;
+    if let Some((_, listed)) = latest_listing(
+        storage,
+        auction.nft.contract_addr.clone(),
+        auction.nft.token_id.clone(),
+    )? {
+        if !listed.is_settled() {
+            return Err(ContractError::NftAlreadyListed {});
+        }
+    }
+
+    let auction_id = NEXT_AUCTION_ID.load(storage)?;
+    NEXT_AUCTION_ID.save(storage, &(auction_id + 1))?;
+    auctions().save(storage, auction_id, auction)?;
+    Ok(auction_id)
+}
 
-    Ok(Response::default())
+/// the most recent auction of an NFT
+fn latest_listing(
+    storage: &dyn Storage,
+    contract_addr: Addr,
+    token_id: String,
+) -> StdResult<Option<(u64, Auction)>> {
+    auctions()
+        .idx
+        .nft
+        .prefix((contract_addr, token_id))
+        .range(storage, None, None, Order::Descending)
+        .next()
+        .transpose()
+}
+
+fn ensure_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    if PAUSED.load(storage)? {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+fn load_auction(storage: &dyn Storage, auction_id: u64) -> Result<Auction, ContractError> {
+    auctions()
+        .may_load(storage, auction_id)?
+        .ok_or(ContractError::AuctionNotFound {})
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -69,43 +147,164 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::PlaceBid {} => place_bid(deps, env, info),
-        ExecuteMsg::EndAuction {} => end_auction(deps, env),
+        ExecuteMsg::PlaceBid { auction_id } => place_bid(deps, env, info, auction_id),
+        ExecuteMsg::EndAuction { auction_id } => end_auction(deps, env, auction_id),
+        ExecuteMsg::CancelAuction { auction_id } => cancel_auction(deps, info, auction_id),
         ExecuteMsg::ReceiveNft(recv_msg) => receive_nft(deps, env, info, recv_msg),
+        ExecuteMsg::Pause {} => set_contract_paused(deps, info, true),
+        ExecuteMsg::Unpause {} => set_contract_paused(deps, info, false),
+        ExecuteMsg::PauseAuction { auction_id } => set_paused(deps, info, auction_id, true),
+        ExecuteMsg::UnpauseAuction { auction_id } => set_paused(deps, info, auction_id, false),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored_version = check_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    if stored_version < v0_2() {
+        let admin = msg
+            .admin
+            .map(|admin| deps.api.addr_validate(&admin))
+            .transpose()?;
+        migrate_from_v0_1(deps.storage, admin)?;
     }
+    if stored_version < v0_3() {
+        migrate_from_v0_2(deps.storage)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, StdError> {
     match msg {
-        QueryMsg::AuctionInfo {} => query_auction_info(deps),
-        QueryMsg::BidHistory { limit, offset } => query_bid_history(deps, limit, offset),
+        QueryMsg::AuctionInfo { auction_id } => query_auction_info(deps, auction_id),
+        QueryMsg::AuctionByNft {
+            contract_addr,
+            token_id,
+        } => query_auction_by_nft(deps, contract_addr, token_id),
+        QueryMsg::Auctions { start_after, limit } => query_auctions(deps, start_after, limit),
+        QueryMsg::BidHistory {
+            auction_id,
+            limit,
+            offset,
+        } => query_bid_history(deps, auction_id, limit, offset),
     }
 }
 
 fn receive_nft(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: Cw721ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    let mut auction: Auction = AUCTION_INFO.load(deps.storage)?;
-
+    ensure_not_paused(deps.storage)?;
     let sender = deps.api.addr_validate(&msg.sender)?;
-    auction.receive_nft(msg.token_id, sender, info.sender)?;
 
-    AUCTION_INFO.save(deps.storage, &auction)?;
+    // without a payload the NFT belongs to a listing opened at instantiation
+    if msg.msg.is_empty() {
+        let (auction_id, mut auction) =
+            latest_listing(deps.storage, info.sender.clone(), msg.token_id.clone())?
+                .filter(|(_, auction)| !auction.is_settled())
+                .ok_or(ContractError::AuctionNotFound {})?;
+        auction.receive_nft(msg.token_id, sender, info.sender)?;
+
+        auctions().save(deps.storage, auction_id, &auction)?;
+
+        return Ok(Response::default()
+            .add_attribute("action", "receive_nft")
+            .add_attribute("auction_id", auction_id.to_string()));
+    }
+
+    match from_binary(&msg.msg)? {
+        ReceiveMsg::CreateAuction {
+            start_time,
+            end_time,
+            reserve_price,
+            dynamic_bidding_extension,
+            denom,
+            fees,
+            payout_address,
+            buy_now_price,
+        } => {
+            let mut nft = Nft::new(msg.token_id, sender.clone(), info.sender);
+            nft.received = true;
+            let payout_address = payout_address
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?
+                .unwrap_or(sender);
+
+            let mut auction = Auction {
+                buy_now_price: buy_now_price.map(Uint128::from),
+                ..Auction::new(
+                    nft,
+                    Timestamp::from_seconds(start_time.into()),
+                    Timestamp::from_seconds(end_time.into()),
+                    Uint128::from(reserve_price),
+                    dynamic_bidding_extension,
+                    denom,
+                    check_fees(deps.api, fees)?,
+                    payout_address,
+                )
+            };
+            // a listing that has already ended could never take a bid
+            if auction.end_time <= env.block.time {
+                return Err(ContractError::AuctionEnded {});
+            }
+
+            // the payload configures a listing opened at instantiation
+            let pending = latest_listing(
+                deps.storage,
+                auction.nft.contract_addr.clone(),
+                auction.nft.token_id.clone(),
+            )?
+            .filter(|(_, listed)| !listed.is_settled() && !listed.nft.received);
+            let auction_id = match pending {
+                Some((auction_id, listed)) => {
+                    if listed.paused {
+                        return Err(ContractError::Paused {});
+                    }
+                    if listed.nft.owner != auction.nft.owner {
+                        return Err(ContractError::NftOwnerMismatch {});
+                    }
+                    // fees set at instantiation, such as a factory's, cannot
+                    // be dropped by the payload, only added to
+                    let mut fees = listed.fees;
+                    fees.append(&mut auction.fees);
+                    auction.fees = fees;
+                    auction.validate()?;
+                    auctions().save(deps.storage, auction_id, &auction)?;
+                    auction_id
+                }
+                None => list_auction(deps.storage, &auction)?,
+            };
 
-    Ok(Response::default())
+            Ok(Response::default()
+                .add_attribute("action", "create_auction")
+                .add_attribute("auction_id", auction_id.to_string())
+                .add_attribute("owner", auction.nft.owner)
+                .add_attribute("token_id", auction.nft.token_id))
+        }
+    }
 }
 
-fn place_bid(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let mut auction: Auction = AUCTION_INFO.load(deps.storage)?;
+fn place_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    ensure_not_paused(deps.storage)?;
+    let mut auction = load_auction(deps.storage, auction_id)?;
     let previous_bid = auction.current_bid.clone();
     let amount = cw_utils::must_pay(&info, &auction.denom)?;
 
     auction.place_bid(info.sender.clone(), amount, env.block.time)?;
-    AUCTION_INFO.save(deps.storage, &auction)?;
+    auctions().save(deps.storage, auction_id, &auction)?;
 
     let bid = Bid {
         bidder: info.sender.clone(),
@@ -113,7 +312,13 @@ fn place_bid(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, Con
         timestamp: env.block.time,
     };
 
-    BID_HISTORY.push_front(deps.storage, &bid)?;
+    let bid_count = BIDS
+        .prefix(auction_id)
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+    BIDS.save(deps.storage, (auction_id, bid_count), &bid)?;
 
     // bank send previous bid back to previous bidder
     let mut bank_messages: Vec<BankMsg> = vec![];
@@ -124,25 +329,43 @@ fn place_bid(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, Con
         });
     }
 
-    Ok(Response::default()
+    // a buy now bid settles the sale along with the bid
+    let response = match &auction.winning_bid {
+        Some(winning_bid) => {
+            sell_nft(auction_id, &auction, winning_bid)?.add_attribute("buy_now", "true")
+        }
+        None => Response::default(),
+    };
+
+    Ok(response
         .add_messages(bank_messages)
         .add_attribute("action", "place_bid")
+        .add_attribute("auction_id", auction_id.to_string())
         .add_attribute("bidder", info.sender.to_string())
         .add_attribute("amount", amount.to_string()))
 }
 
-fn end_auction(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
-    let mut auction: Auction = AUCTION_INFO.load(deps.storage)?;
+fn end_auction(deps: DepsMut, env: Env, auction_id: u64) -> Result<Response, ContractError> {
+    let mut auction = load_auction(deps.storage, auction_id)?;
     auction.end_auction(env.block.time)?;
 
-    AUCTION_INFO.save(deps.storage, &auction)?;
+    auctions().save(deps.storage, auction_id, &auction)?;
 
     let Some(winning_bid) = auction.winning_bid.clone() else {
-        return Err(ContractError::AuctionNotEnded {});
+        return Ok(return_nft(auction_id, &auction, "auction_unsold")?
+            .add_attribute("action", "end_auction")
+            .add_attribute("auction_id", auction_id.to_string()));
     };
 
+    Ok(sell_nft(auction_id, &auction, &winning_bid)?
+        .add_attribute("action", "end_auction")
+        .add_attribute("auction_id", auction_id.to_string()))
+}
+
+/// transfers the NFT to the winning bidder and pays out the winning bid
+fn sell_nft(auction_id: u64, auction: &Auction, winning_bid: &Bid) -> StdResult<Response> {
     let nft_msg = wasm_execute(
-        auction.nft.contract_addr,
+        auction.nft.contract_addr.clone(),
         &cw721::Cw721ExecuteMsg::TransferNft {
             recipient: winning_bid.bidder.to_string(),
             token_id: auction.nft.token_id.clone(),
@@ -150,51 +373,179 @@ fn end_auction(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
         vec![],
     )?;
 
-    // send fees to fee addresses then send whats left to the vault
+    // send fees to fee addresses then send whats left to the vault. the bank
+    // module rejects sends of zero coins, so empty payouts are skipped
     let mut remaining_amount = winning_bid.amount;
     let mut bank_msgs = vec![];
     let mut attrs: Vec<(String, String)> = vec![];
-    for fee in auction.fees {
+    for fee in &auction.fees {
         let fee_amount = Uint128::from(fee.bps) * winning_bid.amount / Uint128::from(10_000_u128);
-        bank_msgs.push(BankMsg::Send {
-            to_address: fee.address.to_string(),
-            amount: coins(fee_amount.u128(), auction.denom.clone()),
-        });
+        if !fee_amount.is_zero() {
+            bank_msgs.push(BankMsg::Send {
+                to_address: fee.address.to_string(),
+                amount: coins(fee_amount.u128(), auction.denom.clone()),
+            });
+        }
         remaining_amount -= fee_amount;
-        attrs.push((fee.label, fee_amount.to_string()));
+        attrs.push((fee.label.clone(), fee_amount.to_string()));
     }
     let fee_dist_event = Event::new("fee_distribution")
+        .add_attribute("auction_id", auction_id.to_string())
         .add_attribute("auction_denom", auction.denom.clone())
         .add_attribute("auction_amount", winning_bid.amount.to_string())
         .add_attributes(attrs);
-    bank_msgs.push(BankMsg::Send {
-        to_address: auction.payout_address.to_string(),
-        amount: coins(remaining_amount.u128(), auction.denom.clone()),
-    });
+    if !remaining_amount.is_zero() {
+        bank_msgs.push(BankMsg::Send {
+            to_address: auction.payout_address.to_string(),
+            amount: coins(remaining_amount.u128(), auction.denom.clone()),
+        });
+    }
     Ok(Response::default()
         .add_message(nft_msg)
         .add_messages(bank_msgs)
-        .add_event(fee_dist_event)
-        .add_attribute("action", "end_auction"))
+        .add_event(fee_dist_event))
+}
+
+fn cancel_auction(
+    deps: DepsMut,
+    info: MessageInfo,
+    auction_id: u64,
+) -> Result<Response, ContractError> {
+    let mut auction = load_auction(deps.storage, auction_id)?;
+    auction.cancel(&info.sender)?;
+    auctions().save(deps.storage, auction_id, &auction)?;
+
+    Ok(return_nft(auction_id, &auction, "auction_cancelled")?
+        .add_attribute("action", "cancel_auction")
+        .add_attribute("auction_id", auction_id.to_string()))
+}
+
+/// announces a settlement without a sale, handing the NFT back to its owner
+/// if it was received
+fn return_nft(auction_id: u64, auction: &Auction, event: &str) -> StdResult<Response> {
+    let event = Event::new(event)
+        .add_attribute("auction_id", auction_id.to_string())
+        .add_attribute("owner", auction.nft.owner.to_string())
+        .add_attribute("contract_addr", auction.nft.contract_addr.to_string())
+        .add_attribute("token_id", auction.nft.token_id.clone())
+        .add_attribute("nft_returned", auction.nft.received.to_string());
+    let response = Response::default().add_event(event);
+    if !auction.nft.received {
+        return Ok(response);
+    }
+
+    let nft_msg = wasm_execute(
+        auction.nft.contract_addr.clone(),
+        &cw721::Cw721ExecuteMsg::TransferNft {
+            recipient: auction.nft.owner.to_string(),
+            token_id: auction.nft.token_id.clone(),
+        },
+        vec![],
+    )?;
+    Ok(response.add_message(nft_msg))
+}
+
+fn set_contract_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PAUSED.save(deps.storage, &paused)?;
+
+    let action = if paused { "pause" } else { "unpause" };
+    Ok(Response::default().add_attribute("action", action))
 }
 
-fn query_auction_info(deps: Deps) -> Result<Binary, StdError> {
-    let auction: Auction = AUCTION_INFO.load(deps.storage)?;
+fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    auction_id: u64,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    if ADMIN.load(deps.storage)? != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut auction = load_auction(deps.storage, auction_id)?;
+    auction.paused = paused;
+    auctions().save(deps.storage, auction_id, &auction)?;
+
+    let action = if paused {
+        "pause_auction"
+    } else {
+        "unpause_auction"
+    };
+    Ok(Response::default()
+        .add_attribute("action", action)
+        .add_attribute("auction_id", auction_id.to_string()))
+}
+
+fn query_auction_info(deps: Deps, auction_id: u64) -> Result<Binary, StdError> {
+    let auction: Auction = auctions().load(deps.storage, auction_id)?;
+    let response = AuctionInfoResponse {
+        auction_id,
+        auction_info: auction,
+        paused: PAUSED.load(deps.storage)?,
+    };
+    to_binary(&response)
+}
+
+fn query_auction_by_nft(
+    deps: Deps,
+    contract_addr: String,
+    token_id: String,
+) -> Result<Binary, StdError> {
+    let contract_addr = deps.api.addr_validate(&contract_addr)?;
+    let (auction_id, auction) = latest_listing(deps.storage, contract_addr, token_id)?
+        .ok_or_else(|| StdError::not_found("auction"))?;
     let response = AuctionInfoResponse {
+        auction_id,
         auction_info: auction,
+        paused: PAUSED.load(deps.storage)?,
     };
     to_binary(&response)
 }
 
+fn query_auctions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, StdError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let paused = PAUSED.load(deps.storage)?;
+    let auctions = auctions()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(auction_id, auction)| AuctionInfoResponse {
+                auction_id,
+                auction_info: auction,
+                paused,
+            })
+        })
+        .collect::<StdResult<Vec<AuctionInfoResponse>>>()?;
+
+    let response = AuctionsResponse { auctions };
+    to_binary(&response)
+}
+
 fn query_bid_history(
     deps: Deps,
+    auction_id: u64,
     limit: Option<u32>,
     offset: Option<u32>,
 ) -> Result<Binary, StdError> {
-    let bids = BID_HISTORY
-        .iter(deps.storage)?
+    let bids = BIDS
+        .prefix(auction_id)
+        .range(deps.storage, None, None, Order::Descending)
         .skip(offset.unwrap_or(0) as usize)
-        .take(limit.unwrap_or(30) as usize)
+        .take(limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize)
+        .map(|item| item.map(|(_, bid)| bid))
         .collect::<StdResult<Vec<Bid>>>()?;
 
     let response = BidHistoryResponse { bid_history: bids };
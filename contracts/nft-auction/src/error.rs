@@ -1,4 +1,4 @@
-
+use contract_upgrade::UpgradeError;
 use cosmwasm_std::StdError;
 use cw_utils::PaymentError;
 use thiserror::Error;
@@ -98,4 +98,25 @@ pub enum ContractError {
 
     #[error("Invalid Uint64")]
     InvalidUint64 {},
+
+    #[error("Buy now price must be above the reserve price")]
+    BuyNowPriceTooLow {},
+
+    #[error("Auction has already been settled")]
+    AuctionSettled {},
+
+    #[error("Auction cannot be cancelled once it has bids")]
+    AuctionHasBids {},
+
+    #[error("Auction not found")]
+    AuctionNotFound {},
+
+    #[error("NFT is already listed in an unsettled auction")]
+    NftAlreadyListed {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("{0}")]
+    Upgrade(#[from] UpgradeError),
 }
@@ -1,6 +1,9 @@
 pub mod auction;
 pub mod contract;
 mod error;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 pub use crate::error::ContractError;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Storage, Timestamp, Uint128};
+use cw_storage_plus::{Deque, Item};
+use semver::Version;
+
+use crate::auction::{Auction, Bid, DynamicBiddingExtension, Fee, Nft};
+use crate::state::{auctions, ADMIN, BIDS, NEXT_AUCTION_ID, PAUSED};
+use crate::ContractError;
+
+/// Auction as stored by 0.1.x
+#[cw_serde]
+pub struct AuctionV0_1 {
+    pub nft: Nft,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    pub reserve_price: Uint128,
+    pub current_bid: Option<Bid>,
+    pub dynamic_bidding_extension: DynamicBiddingExtension,
+    pub denom: String,
+    pub winning_bid: Option<Bid>,
+    pub fees: Vec<Fee>,
+    pub payout_address: Addr,
+}
+
+pub const AUCTION_INFO_V0_1: Item<AuctionV0_1> = Item::new("auction_info");
+
+/// the single auction of 0.2.x
+pub const AUCTION_INFO_V0_2: Item<Auction> = Item::new("auction_info");
+/// the bids of 0.2.x, newest first
+pub const BID_HISTORY_V0_2: Deque<Bid> = Deque::new("bid_history");
+
+impl From<AuctionV0_1> for Auction {
+    fn from(auction: AuctionV0_1) -> Self {
+        Auction {
+            nft: auction.nft,
+            start_time: auction.start_time,
+            end_time: auction.end_time,
+            reserve_price: auction.reserve_price,
+            current_bid: auction.current_bid,
+            dynamic_bidding_extension: auction.dynamic_bidding_extension,
+            denom: auction.denom,
+            winning_bid: auction.winning_bid,
+            fees: auction.fees,
+            payout_address: auction.payout_address,
+            paused: false,
+            settled: false,
+            buy_now_price: None,
+        }
+    }
+}
+
+/// the first version with an admin and pausing
+pub fn v0_2() -> Version {
+    Version::new(0, 2, 0)
+}
+
+/// the first version with the current state layout, holding many auctions
+pub fn v0_3() -> Version {
+    Version::new(0, 3, 0)
+}
+
+/// moves state written by 0.1.x into the 0.2.x layout, unpaused and with
+/// the admin defaulting to the NFT owner
+pub fn migrate_from_v0_1(
+    storage: &mut dyn Storage,
+    admin: Option<Addr>,
+) -> Result<(), ContractError> {
+    let auction: Auction = AUCTION_INFO_V0_1.load(storage)?.into();
+    let admin = admin.unwrap_or_else(|| auction.nft.owner.clone());
+    ADMIN.save(storage, &admin)?;
+    AUCTION_INFO_V0_2.save(storage, &auction)?;
+    Ok(())
+}
+
+/// moves the single auction of 0.2.x and its bids to auction id 0. pausing
+/// that auction paused the whole contract, so its flag becomes the
+/// contract-wide one
+pub fn migrate_from_v0_2(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let mut auction = AUCTION_INFO_V0_2.load(storage)?;
+    AUCTION_INFO_V0_2.remove(storage);
+    PAUSED.save(storage, &auction.paused)?;
+    auction.paused = false;
+    auctions().save(storage, 0, &auction)?;
+    NEXT_AUCTION_ID.save(storage, &1)?;
+
+    // popping from the back replays the bids oldest first
+    let mut bid_count = 0;
+    while let Some(bid) = BID_HISTORY_V0_2.pop_back(storage)? {
+        BIDS.save(storage, (0, bid_count), &bid)?;
+        bid_count += 1;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Order, StdResult, Uint64};
+
+    use super::*;
+
+    #[test]
+    fn test_migrate_from_v0_2() {
+        let mut storage = MockStorage::new();
+        let mut auction = Auction::new(
+            Nft::new(
+                "1".to_string(),
+                Addr::unchecked("owner"),
+                Addr::unchecked("nft"),
+            ),
+            Timestamp::from_seconds(100),
+            Timestamp::from_seconds(200),
+            Uint128::new(100),
+            DynamicBiddingExtension::new(false, Uint64::zero(), Uint128::new(1)),
+            "ujuno".to_string(),
+            vec![],
+            Addr::unchecked("payout"),
+        );
+        auction.paused = true;
+        AUCTION_INFO_V0_2.save(&mut storage, &auction).unwrap();
+        for amount in [100, 150, 200] {
+            let bid = Bid::new(
+                Addr::unchecked("bidder"),
+                Uint128::new(amount),
+                Timestamp::from_seconds(150),
+            );
+            BID_HISTORY_V0_2.push_front(&mut storage, &bid).unwrap();
+        }
+
+        migrate_from_v0_2(&mut storage).unwrap();
+
+        // the paused single auction pauses the whole contract
+        assert!(PAUSED.load(&storage).unwrap());
+        auction.paused = false;
+        assert_eq!(auctions().load(&storage, 0).unwrap(), auction);
+        assert_eq!(NEXT_AUCTION_ID.load(&storage).unwrap(), 1);
+        assert_eq!(AUCTION_INFO_V0_2.may_load(&storage).unwrap(), None);
+        assert_eq!(BID_HISTORY_V0_2.len(&storage).unwrap(), 0);
+        let bids = BIDS
+            .range(&storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|((auction_id, index), bid)| (auction_id, index, bid.amount.u128()))
+            })
+            .collect::<StdResult<Vec<(u64, u64, u128)>>>()
+            .unwrap();
+        assert_eq!(bids, vec![(0, 0, 100), (0, 1, 150), (0, 2, 200)]);
+    }
+}
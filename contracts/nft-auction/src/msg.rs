@@ -21,7 +21,7 @@ pub struct NftUnverified {
 /// All times are in seconds since epoch
 
 #[cw_serde]
-pub struct InstantiateMsg {
+pub struct AuctionUnverified {
     pub nft: NftUnverified,
     pub start_time: Uint64,
     pub end_time: Uint64,
@@ -30,28 +30,100 @@ pub struct InstantiateMsg {
     pub denom: String,
     pub fees: Vec<FeeUnverified>,
     pub payout_address: String,
+    /// a bid of at least this much wins the auction right away
+    pub buy_now_price: Option<Uint64>,
+}
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// a listing opened along with the contract, waiting for its NFT to be
+    /// sent. leave unset to run an auction house whose listings are created
+    /// by sending NFTs with a `ReceiveMsg`
+    pub auction: Option<AuctionUnverified>,
+    /// the address allowed to pause auctions, defaults to the instantiator
+    pub admin: Option<String>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    PlaceBid {},
-    EndAuction {},
+    PlaceBid {
+        auction_id: u64,
+    },
+    /// Settles an ended auction, returning the NFT to its owner if nobody bid
+    EndAuction {
+        auction_id: u64,
+    },
+    /// Cancels an auction before its first bid and returns the NFT, owner only
+    CancelAuction {
+        auction_id: u64,
+    },
     ReceiveNft(Cw721ReceiveMsg),
+    /// Halts bidding and receiving NFTs on every auction, admin only
+    Pause {},
+    /// Resumes the paused contract, admin only
+    Unpause {},
+    /// Halts bidding and receiving the NFT of one auction, admin only
+    PauseAuction {
+        auction_id: u64,
+    },
+    /// Resumes a paused auction, admin only
+    UnpauseAuction {
+        auction_id: u64,
+    },
+}
+
+/// Sent as the `msg` of `SendNft`, escrowing the NFT and configuring its
+/// listing at once. an empty `msg` hands over the NFT of a listing opened at
+/// instantiation as it was configured there
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Lists the sent NFT, the sender becoming its owner. replaces the
+    /// configuration of a listing opened at instantiation for the NFT, apart
+    /// from its fees, which the payload's fees are added to
+    CreateAuction {
+        start_time: Uint64,
+        end_time: Uint64,
+        reserve_price: Uint64,
+        dynamic_bidding_extension: DynamicBiddingExtension,
+        denom: String,
+        fees: Vec<FeeUnverified>,
+        /// defaults to the sender
+        payout_address: Option<String>,
+        /// a bid of at least this much wins the auction right away
+        buy_now_price: Option<Uint64>,
+    },
 }
 
 #[cw_serde]
-pub enum MigrateMsg {}
+pub struct MigrateMsg {
+    /// the admin to set when migrating from a version without one,
+    /// defaults to the NFT owner
+    pub admin: Option<String>,
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// Returns the current state of the auction
     #[returns(AuctionInfoResponse)]
-    AuctionInfo {},
+    AuctionInfo { auction_id: u64 },
+    /// Returns the latest listing of an NFT
+    #[returns(AuctionInfoResponse)]
+    AuctionByNft {
+        contract_addr: String,
+        token_id: String,
+    },
+    /// Lists auctions by id
+    #[returns(AuctionsResponse)]
+    Auctions {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     /// Returns the bid history of the auction
     #[returns(BidHistoryResponse)]
     BidHistory {
-        /// The number of bids to return
+        auction_id: u64,
+        /// The number of bids to return, 10 by default and at most 30
         limit: Option<u32>,
         /// The offset to start from
         offset: Option<u32>,
@@ -60,7 +132,16 @@ pub enum QueryMsg {
 
 #[cw_serde]
 pub struct AuctionInfoResponse {
+    pub auction_id: u64,
     pub auction_info: Auction,
+    /// whether the whole contract is paused, `auction_info.paused` only
+    /// covers this auction
+    pub paused: bool,
+}
+
+#[cw_serde]
+pub struct AuctionsResponse {
+    pub auctions: Vec<AuctionInfoResponse>,
 }
 
 #[cw_serde]
@@ -1,7 +1,42 @@
-use cw_storage_plus::Deque;
-use cw_storage_plus::Item;
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 use crate::auction::{Auction, Bid};
 
-pub const AUCTION_INFO: Item<Auction> = Item::new("auction_info");
-pub const BID_HISTORY: Deque<Bid> = Deque::new("bid_history");
+/// the address allowed to pause and unpause auctions
+pub const ADMIN: Item<Addr> = Item::new("admin");
+/// while set no bids are accepted and no NFTs are received, for every auction
+pub const PAUSED: Item<bool> = Item::new("paused");
+/// the id given to the next auction listed
+pub const NEXT_AUCTION_ID: Item<u64> = Item::new("next_auction_id");
+/// bids by auction id and the number of bids placed before them
+pub const BIDS: Map<(u64, u64), Bid> = Map::new("bids");
+
+pub struct AuctionIndexes<'a> {
+    /// every listing of an NFT by its contract address and token id
+    pub nft: MultiIndex<'a, (Addr, String), Auction, u64>,
+}
+
+impl<'a> IndexList<Auction> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Auction>> + '_> {
+        let v: Vec<&dyn Index<Auction>> = vec![&self.nft];
+        Box::new(v.into_iter())
+    }
+}
+
+/// every auction by id
+pub fn auctions<'a>() -> IndexedMap<'a, u64, Auction, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        nft: MultiIndex::new(
+            |_pk, auction| {
+                (
+                    auction.nft.contract_addr.clone(),
+                    auction.nft.token_id.clone(),
+                )
+            },
+            "auctions",
+            "auctions__nft",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
@@ -0,0 +1,952 @@
+use contract_upgrade::UpgradeError;
+use cosmwasm_std::{
+    coins, from_slice, to_binary, Addr, Binary, DepsMut, Empty, Env, MessageInfo, Response,
+    Timestamp, Uint128, Uint64,
+};
+use cw2::ContractVersion;
+use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, Cw721ReceiveMsg, OwnerOfResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+use crate::auction::{DynamicBiddingExtension, Nft};
+use crate::contract::{execute, instantiate, migrate, query};
+use crate::migrations::{AuctionV0_1, AUCTION_INFO_V0_1};
+use crate::msg::{
+    AuctionInfoResponse, AuctionUnverified, AuctionsResponse, BidHistoryResponse, ExecuteMsg,
+    FeeUnverified, InstantiateMsg, MigrateMsg, NftUnverified, QueryMsg, ReceiveMsg,
+};
+use crate::ContractError;
+
+const CONTRACT_NAME: &str = "crates.io:nft-auction";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub fn nft_auction_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
+    Box::new(contract)
+}
+
+/// stores the auction the way 0.1.0 did
+fn legacy_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, "0.1.0")?;
+    let msg = msg.auction.unwrap();
+    let auction = AuctionV0_1 {
+        nft: Nft::new(
+            msg.nft.token_id,
+            Addr::unchecked(msg.nft.owner),
+            Addr::unchecked(msg.nft.contract_addr),
+        ),
+        start_time: Timestamp::from_seconds(msg.start_time.u64()),
+        end_time: Timestamp::from_seconds(msg.end_time.u64()),
+        reserve_price: Uint128::from(msg.reserve_price),
+        current_bid: None,
+        dynamic_bidding_extension: msg.dynamic_bidding_extension,
+        denom: msg.denom,
+        winning_bid: None,
+        fees: vec![],
+        payout_address: Addr::unchecked(msg.payout_address),
+    };
+    AUCTION_INFO_V0_1.save(deps.storage, &auction)?;
+    Ok(Response::default())
+}
+
+/// instantiates normally, then records a newer version than this build
+fn future_instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let response = instantiate(deps.branch(), env, info, msg)?;
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, "99.0.0")?;
+    Ok(response)
+}
+
+pub fn cw721_base_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    );
+    Box::new(contract)
+}
+
+fn instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        auction: Some(AuctionUnverified {
+            nft: NftUnverified {
+                token_id: "1".to_string(),
+                owner: "seller".to_string(),
+                contract_addr: "nft_contract".to_string(),
+            },
+            start_time: Uint64::new(1_000),
+            end_time: Uint64::new(2_000),
+            reserve_price: Uint64::new(100),
+            dynamic_bidding_extension: DynamicBiddingExtension::new(
+                false,
+                Uint64::zero(),
+                Uint128::new(1),
+            ),
+            denom: "ujuno".to_string(),
+            fees: vec![],
+            payout_address: "payout".to_string(),
+            buy_now_price: None,
+        }),
+        admin: None,
+    }
+}
+
+fn instantiate_with(app: &mut App, contract: Box<dyn Contract<Empty>>, owner: &Addr) -> Addr {
+    let code_id = app.store_code(contract);
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &instantiate_msg(),
+        &[],
+        "nft_auction".to_string(),
+        Some(owner.to_string()),
+    )
+    .unwrap()
+}
+
+/// instantiates a cw721-base collection with token "1" minted to `seller`
+fn instantiate_collection(app: &mut App, minter: &Addr, seller: &Addr) -> Addr {
+    let code_id = app.store_code(cw721_base_contract());
+    let collection = app
+        .instantiate_contract(
+            code_id,
+            minter.clone(),
+            &cw721_base::InstantiateMsg {
+                name: "good boiz".to_string(),
+                symbol: "good boiz".to_string(),
+                minter: minter.to_string(),
+            },
+            &[],
+            "cw721_base".to_string(),
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        minter.clone(),
+        collection.clone(),
+        &cw721_base::msg::ExecuteMsg::<Empty, Empty>::Mint {
+            token_id: "1".to_string(),
+            owner: seller.to_string(),
+            token_uri: None,
+            extension: Empty {},
+        },
+        &[],
+    )
+    .unwrap();
+    collection
+}
+
+/// instantiates an auction house without a listing
+fn instantiate_house(app: &mut App, owner: &Addr) -> Addr {
+    let code_id = app.store_code(nft_auction_contract());
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            auction: None,
+            admin: None,
+        },
+        &[],
+        "nft_auction".to_string(),
+        None,
+    )
+    .unwrap()
+}
+
+/// lists an NFT from now for 100 seconds with a reserve of 100ujuno, paying
+/// out to "payout"
+fn create_auction_msg(
+    app: &App,
+    fees: Vec<FeeUnverified>,
+    buy_now_price: Option<u64>,
+) -> ReceiveMsg {
+    let now = app.block_info().time.seconds();
+    ReceiveMsg::CreateAuction {
+        start_time: Uint64::new(now),
+        end_time: Uint64::new(now + 100),
+        reserve_price: Uint64::new(100),
+        dynamic_bidding_extension: DynamicBiddingExtension::new(
+            false,
+            Uint64::zero(),
+            Uint128::new(1),
+        ),
+        denom: "ujuno".to_string(),
+        fees,
+        payout_address: Some("payout".to_string()),
+        buy_now_price: buy_now_price.map(Uint64::new),
+    }
+}
+
+/// sends token "1" of `collection` to `house`, configured by `payload`
+fn send_nft(app: &mut App, seller: &Addr, collection: &Addr, house: &Addr, payload: &ReceiveMsg) {
+    app.execute_contract(
+        seller.clone(),
+        collection.clone(),
+        &Cw721ExecuteMsg::SendNft {
+            contract: house.to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(payload).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+/// the holder of token "1" of `collection`
+fn owner_of(app: &App, collection: &Addr) -> String {
+    let resp: OwnerOfResponse = app
+        .wrap()
+        .query_wasm_smart(
+            collection,
+            &Cw721QueryMsg::OwnerOf {
+                token_id: "1".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap();
+    resp.owner
+}
+
+#[test]
+fn test_migrate_from_v0_1() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let auction_addr = instantiate_with(
+        &mut app,
+        Box::new(ContractWrapper::new(execute, legacy_instantiate, query)),
+        &owner,
+    );
+    let code_id = app.store_code(nft_auction_contract());
+
+    app.migrate_contract(
+        owner.clone(),
+        auction_addr.clone(),
+        &MigrateMsg { admin: None },
+        code_id,
+    )
+    .unwrap();
+
+    let version: ContractVersion = from_slice(
+        &app.wrap()
+            .query_wasm_raw(auction_addr.clone(), b"contract_info".as_slice())
+            .unwrap()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(version.version, CONTRACT_VERSION);
+
+    let resp: AuctionInfoResponse = app
+        .wrap()
+        .query_wasm_smart(
+            auction_addr.clone(),
+            &QueryMsg::AuctionInfo { auction_id: 0 },
+        )
+        .unwrap();
+    assert!(!resp.paused);
+    assert!(!resp.auction_info.paused);
+    assert_eq!(resp.auction_info.nft.owner, seller);
+    assert_eq!(resp.auction_info.reserve_price, Uint128::new(100));
+
+    // without an admin in the migrate message the NFT owner takes the role
+    let err: ContractError = app
+        .execute_contract(owner, auction_addr.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+    app.execute_contract(seller, auction_addr.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+    let resp: AuctionInfoResponse = app
+        .wrap()
+        .query_wasm_smart(auction_addr, &QueryMsg::AuctionInfo { auction_id: 0 })
+        .unwrap();
+    assert!(resp.paused);
+    assert!(!resp.auction_info.paused);
+}
+
+#[test]
+fn test_migrate_refuses_downgrade() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let auction_addr = instantiate_with(
+        &mut app,
+        Box::new(ContractWrapper::new(execute, future_instantiate, query)),
+        &owner,
+    );
+    let code_id = app.store_code(nft_auction_contract());
+
+    let err: ContractError = app
+        .migrate_contract(owner, auction_addr, &MigrateMsg { admin: None }, code_id)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Upgrade(UpgradeError::CannotMigrateToOlderVersion {
+            stored: "99.0.0".to_string(),
+            version: CONTRACT_VERSION.to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_auction_house() {
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let collection = Addr::unchecked("collection");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &bidder, coins(1_000, "ujuno"))
+            .unwrap();
+    });
+    let code_id = app.store_code(nft_auction_contract());
+    let house = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &InstantiateMsg {
+                auction: None,
+                admin: None,
+            },
+            &[],
+            "nft_auction".to_string(),
+            None,
+        )
+        .unwrap();
+
+    let now = app.block_info().time.seconds();
+    let create_auction = |token_id: &str| {
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: seller.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_binary(&ReceiveMsg::CreateAuction {
+                start_time: Uint64::new(now),
+                end_time: Uint64::new(now + 100),
+                reserve_price: Uint64::new(100),
+                dynamic_bidding_extension: DynamicBiddingExtension::new(
+                    false,
+                    Uint64::zero(),
+                    Uint128::new(1),
+                ),
+                denom: "ujuno".to_string(),
+                fees: vec![],
+                payout_address: None,
+                buy_now_price: None,
+            })
+            .unwrap(),
+        })
+    };
+    app.execute_contract(collection.clone(), house.clone(), &create_auction("1"), &[])
+        .unwrap();
+    app.execute_contract(collection.clone(), house.clone(), &create_auction("2"), &[])
+        .unwrap();
+
+    // an NFT cannot be listed again before its auction is settled
+    let err: ContractError = app
+        .execute_contract(collection.clone(), house.clone(), &create_auction("1"), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NftAlreadyListed {});
+
+    for (auction_id, amount) in [(1, 150), (1, 200), (0, 100)] {
+        app.execute_contract(
+            bidder.clone(),
+            house.clone(),
+            &ExecuteMsg::PlaceBid { auction_id },
+            &coins(amount, "ujuno"),
+        )
+        .unwrap();
+    }
+
+    let resp: BidHistoryResponse = app
+        .wrap()
+        .query_wasm_smart(
+            house.clone(),
+            &QueryMsg::BidHistory {
+                auction_id: 1,
+                limit: None,
+                offset: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        resp.bid_history
+            .iter()
+            .map(|bid| bid.amount.u128())
+            .collect::<Vec<u128>>(),
+        vec![200, 150]
+    );
+
+    let resp: AuctionInfoResponse = app
+        .wrap()
+        .query_wasm_smart(
+            house.clone(),
+            &QueryMsg::AuctionByNft {
+                contract_addr: collection.to_string(),
+                token_id: "2".to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!(resp.auction_id, 1);
+    assert_eq!(resp.auction_info.nft.owner, seller);
+    assert_eq!(resp.auction_info.payout_address, seller);
+    assert_eq!(
+        resp.auction_info.current_bid.unwrap().amount,
+        Uint128::new(200)
+    );
+
+    let resp: AuctionsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            house.clone(),
+            &QueryMsg::Auctions {
+                start_after: Some(0),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        resp.auctions
+            .iter()
+            .map(|auction| auction.auction_id)
+            .collect::<Vec<u64>>(),
+        vec![1]
+    );
+
+    // pausing one listing leaves the others open
+    app.execute_contract(
+        owner.clone(),
+        house.clone(),
+        &ExecuteMsg::PauseAuction { auction_id: 0 },
+        &[],
+    )
+    .unwrap();
+    let err: ContractError = app
+        .execute_contract(
+            bidder.clone(),
+            house.clone(),
+            &ExecuteMsg::PlaceBid { auction_id: 0 },
+            &coins(150, "ujuno"),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused {});
+    app.execute_contract(
+        bidder.clone(),
+        house.clone(),
+        &ExecuteMsg::PlaceBid { auction_id: 1 },
+        &coins(250, "ujuno"),
+    )
+    .unwrap();
+
+    // pausing the contract halts bids and new listings on every auction
+    app.execute_contract(owner.clone(), house.clone(), &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+    let err: ContractError = app
+        .execute_contract(
+            bidder.clone(),
+            house.clone(),
+            &ExecuteMsg::PlaceBid { auction_id: 1 },
+            &coins(300, "ujuno"),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused {});
+    let err: ContractError = app
+        .execute_contract(collection.clone(), house.clone(), &create_auction("3"), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Paused {});
+    let resp: AuctionInfoResponse = app
+        .wrap()
+        .query_wasm_smart(house.clone(), &QueryMsg::AuctionInfo { auction_id: 1 })
+        .unwrap();
+    assert!(resp.paused);
+    assert!(!resp.auction_info.paused);
+    app.execute_contract(owner, house.clone(), &ExecuteMsg::Unpause {}, &[])
+        .unwrap();
+
+    let err: ContractError = app
+        .execute_contract(
+            bidder.clone(),
+            house,
+            &ExecuteMsg::PlaceBid { auction_id: 2 },
+            &coins(150, "ujuno"),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionNotFound {});
+
+    // outbid bids are refunded
+    let balance = app.wrap().query_balance(bidder, "ujuno").unwrap();
+    assert_eq!(balance.amount, Uint128::new(650));
+}
+
+#[test]
+fn test_buy_now_over_existing_bid() {
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let buyer = Addr::unchecked("buyer");
+    let mut app = App::new(|router, _, storage| {
+        for account in [&bidder, &buyer] {
+            router
+                .bank
+                .init_balance(storage, account, coins(1_000, "ujuno"))
+                .unwrap();
+        }
+    });
+
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+    let house = instantiate_house(&mut app, &owner);
+    let fees = vec![FeeUnverified {
+        bps: Uint64::new(500),
+        address: "artist".to_string(),
+        label: "royalty".to_string(),
+    }];
+    let payload = create_auction_msg(&app, fees, Some(800));
+    send_nft(&mut app, &seller, &collection, &house, &payload);
+
+    app.execute_contract(
+        bidder.clone(),
+        house.clone(),
+        &ExecuteMsg::PlaceBid { auction_id: 0 },
+        &coins(200, "ujuno"),
+    )
+    .unwrap();
+    let response = app
+        .execute_contract(
+            buyer.clone(),
+            house.clone(),
+            &ExecuteMsg::PlaceBid { auction_id: 0 },
+            &coins(800, "ujuno"),
+        )
+        .unwrap();
+
+    // the fee and the payout are sent as in any other sale
+    let fee_distribution = response
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-fee_distribution")
+        .unwrap();
+    assert!(fee_distribution
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "royalty" && attr.value == "40"));
+    let balance = |app: &App, address: &str| {
+        app.wrap()
+            .query_balance(address, "ujuno")
+            .unwrap()
+            .amount
+            .u128()
+    };
+    assert_eq!(balance(&app, "artist"), 40);
+    assert_eq!(balance(&app, "payout"), 760);
+    assert_eq!(balance(&app, house.as_str()), 0);
+
+    // the outbid bidder is refunded and the buyer holds the NFT
+    assert_eq!(balance(&app, bidder.as_str()), 1_000);
+    assert_eq!(balance(&app, buyer.as_str()), 200);
+    assert_eq!(owner_of(&app, &collection), buyer.to_string());
+
+    let resp: AuctionInfoResponse = app
+        .wrap()
+        .query_wasm_smart(house.clone(), &QueryMsg::AuctionInfo { auction_id: 0 })
+        .unwrap();
+    assert!(resp.auction_info.is_settled());
+    assert_eq!(resp.auction_info.winning_bid.unwrap().bidder, buyer);
+    let err: ContractError = app
+        .execute_contract(
+            bidder,
+            house,
+            &ExecuteMsg::EndAuction { auction_id: 0 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionSettled {});
+}
+
+#[test]
+fn test_buy_now_with_zero_fee() {
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let buyer = Addr::unchecked("buyer");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &buyer, coins(1_000, "ujuno"))
+            .unwrap();
+    });
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+    let house = instantiate_house(&mut app, &owner);
+    let fees = vec![FeeUnverified {
+        bps: Uint64::zero(),
+        address: "artist".to_string(),
+        label: "royalty".to_string(),
+    }];
+    let payload = create_auction_msg(&app, fees, Some(800));
+    send_nft(&mut app, &seller, &collection, &house, &payload);
+
+    // the zero fee is left out rather than sent as zero coins
+    let response = app
+        .execute_contract(
+            buyer.clone(),
+            house,
+            &ExecuteMsg::PlaceBid { auction_id: 0 },
+            &coins(800, "ujuno"),
+        )
+        .unwrap();
+    assert!(!response.events.iter().any(|event| {
+        event.ty == "transfer"
+            && event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "recipient" && attr.value == "artist")
+    }));
+    let balance = |address: &str| {
+        app.wrap()
+            .query_balance(address, "ujuno")
+            .unwrap()
+            .amount
+            .u128()
+    };
+    assert_eq!(balance("artist"), 0);
+    assert_eq!(balance("payout"), 800);
+    assert_eq!(owner_of(&app, &collection), buyer.to_string());
+}
+
+#[test]
+fn test_bid_history_limit_is_capped() {
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &bidder, coins(1_000, "ujuno"))
+            .unwrap();
+    });
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+    let house = instantiate_house(&mut app, &owner);
+    let payload = create_auction_msg(&app, vec![], None);
+    send_nft(&mut app, &seller, &collection, &house, &payload);
+
+    // each bid refunds the last, so one bidder can outbid themselves
+    for amount in 100..140 {
+        app.execute_contract(
+            bidder.clone(),
+            house.clone(),
+            &ExecuteMsg::PlaceBid { auction_id: 0 },
+            &coins(amount, "ujuno"),
+        )
+        .unwrap();
+    }
+
+    let bid_history_len = |limit: Option<u32>| {
+        let resp: BidHistoryResponse = app
+            .wrap()
+            .query_wasm_smart(
+                house.clone(),
+                &QueryMsg::BidHistory {
+                    auction_id: 0,
+                    limit,
+                    offset: None,
+                },
+            )
+            .unwrap();
+        resp.bid_history.len()
+    };
+    assert_eq!(bid_history_len(None), 10);
+    assert_eq!(bid_history_len(Some(100)), 30);
+}
+
+#[test]
+fn test_cancel_returns_nft() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+    let house = instantiate_house(&mut app, &owner);
+    let payload = create_auction_msg(&app, vec![], None);
+    send_nft(&mut app, &seller, &collection, &house, &payload);
+    assert_eq!(owner_of(&app, &collection), house.to_string());
+
+    app.execute_contract(
+        seller.clone(),
+        house,
+        &ExecuteMsg::CancelAuction { auction_id: 0 },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(owner_of(&app, &collection), seller.to_string());
+}
+
+#[test]
+fn test_unsold_auction_returns_nft() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+    let house = instantiate_house(&mut app, &owner);
+    let payload = create_auction_msg(&app, vec![], None);
+    send_nft(&mut app, &seller, &collection, &house, &payload);
+
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    let resp = app
+        .execute_contract(owner, house, &ExecuteMsg::EndAuction { auction_id: 0 }, &[])
+        .unwrap();
+    assert!(resp
+        .events
+        .iter()
+        .any(|event| event.ty == "wasm-auction_unsold"));
+    assert_eq!(owner_of(&app, &collection), seller.to_string());
+}
+
+#[test]
+fn test_payload_keeps_instantiated_fees() {
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let bidder = Addr::unchecked("bidder");
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &bidder, coins(1_000, "ujuno"))
+            .unwrap();
+    });
+    let collection = instantiate_collection(&mut app, &owner, &seller);
+
+    // a listing opened at instantiation with a platform fee and a royalty
+    let mut msg = instantiate_msg();
+    let listing = msg.auction.as_mut().unwrap();
+    listing.nft.contract_addr = collection.to_string();
+    listing.fees = vec![
+        FeeUnverified {
+            bps: Uint64::new(250),
+            address: "platform".to_string(),
+            label: "platform".to_string(),
+        },
+        FeeUnverified {
+            bps: Uint64::new(500),
+            address: "artist".to_string(),
+            label: "royalty".to_string(),
+        },
+    ];
+    let code_id = app.store_code(nft_auction_contract());
+    let auction_addr = app
+        .instantiate_contract(
+            code_id,
+            owner.clone(),
+            &msg,
+            &[],
+            "nft_auction".to_string(),
+            None,
+        )
+        .unwrap();
+
+    // the payload reconfigures the listing without any fees of its own
+    let payload = create_auction_msg(&app, vec![], None);
+    send_nft(&mut app, &seller, &collection, &auction_addr, &payload);
+
+    app.execute_contract(
+        bidder.clone(),
+        auction_addr.clone(),
+        &ExecuteMsg::PlaceBid { auction_id: 0 },
+        &coins(1_000, "ujuno"),
+    )
+    .unwrap();
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(100);
+    });
+    app.execute_contract(
+        owner,
+        auction_addr,
+        &ExecuteMsg::EndAuction { auction_id: 0 },
+        &[],
+    )
+    .unwrap();
+
+    let balance = |address: &str| {
+        app.wrap()
+            .query_balance(address, "ujuno")
+            .unwrap()
+            .amount
+            .u128()
+    };
+    assert_eq!(balance("platform"), 25);
+    assert_eq!(balance("artist"), 50);
+    assert_eq!(balance("payout"), 925);
+    assert_eq!(owner_of(&app, &collection), bidder.to_string());
+}
+
+#[test]
+fn test_receive_nft_configures_listing() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let collection = Addr::unchecked("nft_contract");
+    let auction_addr = instantiate_with(&mut app, nft_auction_contract(), &owner);
+
+    let now = app.block_info().time.seconds();
+    let send_nft = |sender: &Addr, end_time: u64| {
+        ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+            sender: sender.to_string(),
+            token_id: "1".to_string(),
+            msg: to_binary(&ReceiveMsg::CreateAuction {
+                start_time: Uint64::new(now),
+                end_time: Uint64::new(end_time),
+                reserve_price: Uint64::new(500),
+                dynamic_bidding_extension: DynamicBiddingExtension::new(
+                    true,
+                    Uint64::new(60),
+                    Uint128::new(10),
+                ),
+                denom: "ujuno".to_string(),
+                fees: vec![],
+                payout_address: None,
+                buy_now_price: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    // only the owner named at instantiation configures the listing
+    let err: ContractError = app
+        .execute_contract(
+            collection.clone(),
+            auction_addr.clone(),
+            &send_nft(&owner, now + 100),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NftOwnerMismatch {});
+
+    let err: ContractError = app
+        .execute_contract(
+            collection.clone(),
+            auction_addr.clone(),
+            &send_nft(&seller, now),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionEnded {});
+
+    app.execute_contract(
+        collection,
+        auction_addr.clone(),
+        &send_nft(&seller, now + 100),
+        &[],
+    )
+    .unwrap();
+
+    let resp: AuctionsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            auction_addr,
+            &QueryMsg::Auctions {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(resp.auctions.len(), 1);
+    let auction = &resp.auctions[0];
+    assert_eq!(auction.auction_id, 0);
+    assert!(auction.auction_info.nft.received);
+    assert_eq!(auction.auction_info.reserve_price, Uint128::new(500));
+    assert_eq!(
+        auction.auction_info.end_time,
+        Timestamp::from_seconds(now + 100)
+    );
+    assert_eq!(auction.auction_info.payout_address, seller);
+}
+
+#[test]
+fn test_cancel_listing_before_nft_received() {
+    let mut app = App::default();
+    let owner = Addr::unchecked("owner");
+    let seller = Addr::unchecked("seller");
+    let auction_addr = instantiate_with(&mut app, nft_auction_contract(), &owner);
+
+    let err: ContractError = app
+        .execute_contract(
+            owner,
+            auction_addr.clone(),
+            &ExecuteMsg::CancelAuction { auction_id: 0 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let resp = app
+        .execute_contract(
+            seller.clone(),
+            auction_addr.clone(),
+            &ExecuteMsg::CancelAuction { auction_id: 0 },
+            &[],
+        )
+        .unwrap();
+    let event = resp
+        .events
+        .iter()
+        .find(|event| event.ty == "wasm-auction_cancelled")
+        .unwrap();
+    assert!(event
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "nft_returned" && attr.value == "false"));
+
+    // the cancelled listing no longer takes its NFT
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked("nft_contract"),
+            auction_addr.clone(),
+            &ExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: seller.to_string(),
+                token_id: "1".to_string(),
+                msg: Binary::default(),
+            }),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionNotFound {});
+
+    let err: ContractError = app
+        .execute_contract(
+            seller,
+            auction_addr,
+            &ExecuteMsg::EndAuction { auction_id: 0 },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AuctionSettled {});
+}
@@ -0,0 +1,91 @@
+use cosmwasm_std::{StdError, Storage};
+use semver::Version;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum UpgradeError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Cannot migrate {stored} to {expected}")]
+    WrongContract { expected: String, stored: String },
+
+    #[error("Cannot migrate from version {stored} to older version {version}")]
+    CannotMigrateToOlderVersion { stored: String, version: String },
+
+    #[error("Invalid version {version}")]
+    InvalidVersion { version: String },
+}
+
+/// ensures the stored contract is `contract` at `version` or older, returning
+/// the stored version
+pub fn check_upgrade(
+    storage: &dyn Storage,
+    contract: &str,
+    version: &str,
+) -> Result<Version, UpgradeError> {
+    let stored = cw2::get_contract_version(storage)?;
+    if stored.contract != contract {
+        return Err(UpgradeError::WrongContract {
+            expected: contract.to_string(),
+            stored: stored.contract,
+        });
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(version)? {
+        return Err(UpgradeError::CannotMigrateToOlderVersion {
+            stored: stored.version,
+            version: version.to_string(),
+        });
+    }
+    Ok(stored_version)
+}
+
+fn parse_version(version: &str) -> Result<Version, UpgradeError> {
+    version.parse().map_err(|_e| UpgradeError::InvalidVersion {
+        version: version.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+
+    #[test]
+    fn test_check_upgrade() {
+        let mut storage = MockStorage::new();
+        cw2::set_contract_version(&mut storage, "crates.io:vault", "0.1.0").unwrap();
+
+        assert_eq!(
+            check_upgrade(&storage, "crates.io:vault", "0.2.0"),
+            Ok(Version::new(0, 1, 0))
+        );
+        assert_eq!(
+            check_upgrade(&storage, "crates.io:vault", "0.1.0"),
+            Ok(Version::new(0, 1, 0))
+        );
+        assert_eq!(
+            check_upgrade(&storage, "crates.io:vault", "0.0.9"),
+            Err(UpgradeError::CannotMigrateToOlderVersion {
+                stored: "0.1.0".to_string(),
+                version: "0.0.9".to_string(),
+            })
+        );
+        assert_eq!(
+            check_upgrade(&storage, "crates.io:other", "0.2.0"),
+            Err(UpgradeError::WrongContract {
+                expected: "crates.io:other".to_string(),
+                stored: "crates.io:vault".to_string(),
+            })
+        );
+        assert_eq!(
+            check_upgrade(&storage, "crates.io:vault", "latest"),
+            Err(UpgradeError::InvalidVersion {
+                version: "latest".to_string(),
+            })
+        );
+    }
+}